@@ -1,5 +1,6 @@
 use flume::Sender;
 use log::debug;
+use rusqlite::types::Value;
 
 use crate::{ActorSqlError, EResult, Result, model::*};
 
@@ -43,6 +44,61 @@ impl ActorSqliteConnClient {
         }
     }
 
+    /// Run an insert, update or delete with a `returning` clause and collect
+    /// the returned rows.
+    pub async fn execute_returning<S: Into<String>>(
+        &self,
+        sql: S,
+        params: SqlValueVec,
+    ) -> Result<Vec<ActorSqliteRow>> {
+        self.query(sql, params).await
+    }
+
+    /// Run a page query and its count query in one transaction so the total
+    /// matches the page. The count query must return the total in the first
+    /// column of its only row.
+    pub async fn query_page<S: Into<String>, C: Into<String>>(
+        &mut self,
+        sql: S,
+        params: SqlValueVec,
+        count_sql: C,
+        count_params: SqlValueVec,
+    ) -> Result<(Vec<ActorSqliteRow>, i64)> {
+        let tx = self.transaction().await?;
+        let page = match tx.query(sql.into(), params).await {
+            Ok(page) => page,
+            Err(err) => {
+                if let Err(rollback) = tx.rollback().await {
+                    log::warn!("query_page -> unable to rollback {rollback}");
+                }
+                return Err(err);
+            }
+        };
+        let total = match tx.query(count_sql.into(), count_params).await {
+            Ok(rows) => rows
+                .first()
+                .and_then(|row| row.cells.first())
+                .and_then(|(_, value)| match value {
+                    Value::Integer(total) => Some(*total),
+                    _ => None,
+                }),
+            Err(err) => {
+                if let Err(rollback) = tx.rollback().await {
+                    log::warn!("query_page -> unable to rollback {rollback}");
+                }
+                return Err(err);
+            }
+        };
+        tx.commit().await?;
+
+        match total {
+            Some(total) => Ok((page, total)),
+            None => Err(ActorSqlError::RusqliteBuildError(
+                "count query returns no total".to_owned(),
+            )),
+        }
+    }
+
     pub async fn transaction(&mut self) -> Result<ActorSqliteTxClient> {
         match self.inner(ConnCmdReq::Transaction).await? {
             ConnCmdRsp::Tx(tx) => Ok(ActorSqliteTxClient { inner: tx }),
@@ -71,6 +127,16 @@ impl ActorSqliteTxClient {
         }
     }
 
+    /// Run an insert, update or delete with a `returning` clause and collect
+    /// the returned rows.
+    pub async fn execute_returning(
+        &self,
+        sql: String,
+        params: SqlValueVec,
+    ) -> Result<Vec<ActorSqliteRow>> {
+        self.query(sql, params).await
+    }
+
     pub async fn query(&self, sql: String, params: SqlValueVec) -> Result<Vec<ActorSqliteRow>> {
         match self
             .inner(TxCmdReq::Command(CmdReq::QueryMap { sql, params }))
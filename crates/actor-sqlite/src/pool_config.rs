@@ -1,5 +1,9 @@
 use flume::Receiver;
-use rusqlite::{Connection, OpenFlags};
+use rusqlite::{
+    Connection, OpenFlags,
+    functions::FunctionFlags,
+    types::{Value, ValueRef},
+};
 use std::{
     path::{Path, PathBuf},
     thread,
@@ -95,6 +99,8 @@ impl PoolConfig {
             Connection::open_with_flags(path, self.flags)?
         };
 
+        register_unicode_case(&conn)?;
+
         if let Some(journal_mode) = self.journal_mode.take() {
             let val = journal_mode.as_str();
             let out: String =
@@ -109,3 +115,27 @@ impl PoolConfig {
         Ok(conn)
     }
 }
+
+/// Collation comparing text case-insensitively beyond ASCII.
+pub const UNICODE_NOCASE: &str = "unicode_nocase";
+
+/// Unicode aware `lower`, chin-sql's `Wheres::unicode_ilike` is rendered
+/// with it on SQLite.
+pub const UNICODE_LOWER: &str = "unicode_lower";
+
+/// Add the [`UNICODE_LOWER`] function and the [`UNICODE_NOCASE`] collation,
+/// so `unicode_lower(name) like unicode_lower(?)` matches `Ä` with `ä` the
+/// same way Postgres' `ilike` does. The built-in `lower` is left ASCII only.
+/// Done for every pooled connection.
+pub fn register_unicode_case(conn: &Connection) -> rusqlite::Result<()> {
+    let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+    conn.create_scalar_function(UNICODE_LOWER, 1, flags, |ctx| {
+        Ok(match ctx.get_raw(0) {
+            ValueRef::Text(s) => Value::Text(String::from_utf8_lossy(s).to_lowercase()),
+            v => v.into(),
+        })
+    })?;
+    conn.create_collation(UNICODE_NOCASE, |a, b| {
+        a.to_lowercase().cmp(&b.to_lowercase())
+    })
+}
@@ -1,4 +1,5 @@
 use actor_sqlite::pool_config::PoolConfig;
+use chin_sql::{DbType, ILikeType, IntoSqlSeg, SqlBuilder, Wheres};
 
 #[tokio::test]
 async fn test1() {
@@ -19,3 +20,131 @@ async fn test1() {
         println!("error: {r:?}")
     }
 }
+
+#[tokio::test]
+async fn query_page() {
+    let path = std::env::temp_dir().join(format!("actor-sqlite-page-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let pool =
+        actor_sqlite::pool::ActorSqlitePool::try_from(PoolConfig::default().path(&path)).unwrap();
+    let mut client = pool.get().await.unwrap();
+    client
+        .execute("create table note(id integer not null)", vec![])
+        .await
+        .unwrap();
+    for id in 0..5 {
+        client
+            .execute("insert into note values (?)", vec![id.into()])
+            .await
+            .unwrap();
+    }
+
+    let (page, total) = client
+        .query_page(
+            "select id from note where id > ? order by id limit 2",
+            vec![0.into()],
+            "select count(*) as total from note where id > ?",
+            vec![0.into()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(2, page.len());
+    assert_eq!(4, total);
+
+    // the count query fails, its error is returned and the tx rolled back
+    let err = client
+        .query_page(
+            "select id from note limit 2",
+            vec![],
+            format!("select count(*) from {}", "missing"),
+            vec![],
+        )
+        .await
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("no such table: missing"));
+    client
+        .execute("insert into note values (?)", vec![5.into()])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn execute_returning() {
+    let path =
+        std::env::temp_dir().join(format!("actor-sqlite-returning-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let pool =
+        actor_sqlite::pool::ActorSqlitePool::try_from(PoolConfig::default().path(&path)).unwrap();
+    let client = pool.get().await.unwrap();
+    client
+        .execute(
+            "create table note(id integer primary key autoincrement, name text not null)",
+            vec![],
+        )
+        .await
+        .unwrap();
+
+    let rows = client
+        .execute_returning(
+            "insert into note(name) values (?), (?) returning id",
+            vec!["a".to_owned().into(), "b".to_owned().into()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(2, rows.len());
+
+    let rows = client
+        .execute_returning(
+            "delete from note where name = ? returning id, name",
+            vec!["a".to_owned().into()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(1, rows.len());
+    assert_eq!(2, rows[0].cells.len());
+}
+
+#[tokio::test]
+async fn unicode_case() {
+    let pool = actor_sqlite::pool::ActorSqlitePool::try_from(
+        PoolConfig::default().path(":memory:").pool_size(1),
+    )
+    .unwrap();
+    let client = pool.get().await.unwrap();
+    client
+        .execute("create table note(name text not null)", vec![])
+        .await
+        .unwrap();
+    for name in ["Ärger", "ärmel", "Arm", "100%"] {
+        client
+            .execute("insert into note values (?)", vec![name.to_owned().into()])
+            .await
+            .unwrap();
+    }
+
+    let ilike = async |text: &str, ilike_type| {
+        let seg = SqlBuilder::new()
+            .seg("select name from note")
+            .r#where(Wheres::unicode_ilike("name", text, ilike_type))
+            .into_sql_seg(DbType::Sqlite)
+            .unwrap();
+        client
+            .query(seg.seg, seg.values.into_iter().map(Into::into).collect())
+            .await
+            .unwrap()
+            .len()
+    };
+    assert_eq!(2, ilike("äR", ILikeType::RightFuzzy).await);
+    assert_eq!(1, ilike("ÄRMEL", ILikeType::Original).await);
+    assert_eq!(1, ilike("0%", ILikeType::LeftFuzzy).await);
+
+    let rows = client
+        .query(
+            "select name from note where name = ? collate unicode_nocase",
+            vec!["ÄRMEL".to_owned().into()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(1, rows.len());
+}
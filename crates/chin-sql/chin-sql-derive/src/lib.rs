@@ -2,7 +2,7 @@ use proc_macro::TokenStream;
 
 mod table_schema;
 
-#[proc_macro_derive(GenerateTableSchema, attributes(gts_primary, gts_type, gts_key, gts_unique, gts_tosql))]
+#[proc_macro_derive(GenerateTableSchema, attributes(gts_primary, gts_type, gts_key, gts_unique, gts_fts, gts_tosql, gts_fromsql))]
 pub fn generate_table_schema(input: TokenStream) -> TokenStream {
     table_schema::generate_table_schema(input)
 }
\ No newline at end of file
@@ -27,7 +27,9 @@ pub(crate) struct FieldInfo {
     pub not_null: bool,
     pub key_map: HashMap<String, (bool, KeyOrder)>, // key_name(lower), unique?, keyorder
     pub pkey: Option<KeyOrder>,
-    pub to_sql_func: Option<String>
+    pub fts: bool,
+    pub to_sql_func: Option<String>,
+    pub from_sql_func: Option<String>,
 }
 
 pub(crate) fn parse_field_info(field: &Field) -> Result<FieldInfo, syn::Error> {
@@ -58,8 +60,16 @@ pub(crate) fn parse_field_info(field: &Field) -> Result<FieldInfo, syn::Error> {
     }?;
 
     let pkey = find_pkey(field)?;
+    let fts = field.attrs.iter().any(|attr| attr.path().is_ident("gts_fts"));
+    if fts && !matches!(field_type, LogicFieldType::Text) {
+        return Err(syn::Error::new(
+            field.span(),
+            "gts_fts is only allowed on Text columns",
+        ));
+    }
     let key_map = find_attr_key(&column_name, field)?;
-    let to_sql_func = find_to_sql_func(field)?;
+    let to_sql_func = find_sql_func(field, "gts_tosql")?;
+    let from_sql_func = find_sql_func(field, "gts_fromsql")?;
 
     Ok(FieldInfo {
         column_name,
@@ -67,7 +77,9 @@ pub(crate) fn parse_field_info(field: &Field) -> Result<FieldInfo, syn::Error> {
         not_null,
         key_map,
         pkey,
-        to_sql_func
+        fts,
+        to_sql_func,
+        from_sql_func,
     })
 }
 
@@ -117,6 +129,8 @@ fn parse_field_type(
                         )
                     })?;
                     chin_sql::LogicFieldType::Varchar(bound)
+                } else if rt.starts_with("Json<") && rt.ends_with(">") {
+                    chin_sql::LogicFieldType::Json
                 } else {
                     Err(syn::Error::new(
                         field.span(),
@@ -199,9 +213,9 @@ fn find_pkey(field: &Field) -> Result<Option<KeyOrder>, syn::Error> {
     Ok(None)
 }
 
-fn find_to_sql_func(field: &Field) -> Result<Option<String>, syn::Error> {
+fn find_sql_func(field: &Field, attr_name: &str) -> Result<Option<String>, syn::Error> {
     for attr in &field.attrs {
-        if attr.path().is_ident("gts_tosql") {
+        if attr.path().is_ident(attr_name) {
             let meta = &attr.meta;
             if let syn::Meta::NameValue(name_value) = meta {
                 if let syn::Expr::Lit(lit) = &name_value.value {
@@ -62,7 +62,7 @@ pub(crate) fn generate_table_schema(input: TokenStream) -> TokenStream {
 
         impl<'a> chin_sql::SqlTable<'a> for #table_struct_ident<'a> {
                 fn table_expr(&self) -> chin_sql::SqlBuilder<'a> {
-                    #table_name.into()
+                    chin_sql::SqlBuilder::new().ident(#table_name)
                 }
 
                 fn alias(&self) -> &'a str {
@@ -104,6 +104,15 @@ pub(crate) fn generate_table_schema(input: TokenStream) -> TokenStream {
         }
     };
 
+    let row_decoder = match generate_from_sql_row(struct_name, &fields.iter().collect()) {
+        Ok(ok) => ok,
+        Err(err) => {
+            return syn::Error::new(input.span(), format!("GenerateTableSchema error: {err}"))
+                .to_compile_error()
+                .into();
+        }
+    };
+
     let expanded = quote! {
         #table_struct
 
@@ -114,6 +123,7 @@ pub(crate) fn generate_table_schema(input: TokenStream) -> TokenStream {
             #functions
         }
 
+        #row_decoder
     };
 
     TokenStream::from(expanded)
@@ -159,6 +169,7 @@ fn generate_inner(
                 quote! { chin_sql::LogicFieldType::Timestamptz }
             }
             chin_sql::LogicFieldType::Timestamp => quote! {chin_sql::LogicFieldType::Timestamp },
+            chin_sql::LogicFieldType::Json => quote! { chin_sql::LogicFieldType::Json },
         };
 
         column_structs.extend(quote! {
@@ -171,6 +182,12 @@ fn generate_inner(
         all_fields.extend(quote! {#column_name, });
     }
 
+    let fts_fields: Vec<&str> = fields
+        .iter()
+        .filter(|(fi, _)| fi.fts)
+        .map(|(fi, _)| fi.column_name.as_str())
+        .collect();
+
     fn all_same_order<T, F>(eles: &[T], f: F) -> i32
     where
         F: Fn(&T) -> KeyOrder,
@@ -265,6 +282,14 @@ fn generate_inner(
         key_schema.extend(quote! {  ( #key_name, &[#fss]), });
     }
 
+    if !fts_fields.is_empty() {
+        functions.extend(quote! {
+            pub fn full_text() -> chin_sql::FullText<'static> {
+                chin_sql::FullText::new(#table_name, [ #(#fts_fields),* ])
+            }
+        });
+    }
+
     Ok(quote! {
         #[inline]
         pub fn create_sql() -> &'static chin_sql::CreateTableSql {
@@ -273,7 +298,8 @@ fn generate_inner(
                 fields: &[ #column_structs ],
                 pkey: &[ #pkey_schema ],
                 unikeys: &[ #unikey_schema ],
-                keys: &[ #key_schema ]
+                keys: &[ #key_schema ],
+                fts: &[ #(#fts_fields),* ]
             }
         }
 
@@ -309,7 +335,10 @@ fn camel2snake(name: &str) -> String {
     table_name
 }
 
+/// `try_to_sql_inserter` instead when a `Json<T>` field has to be
+/// serialized, which may fail.
 fn to_sql_inserter(fields: &Vec<(FieldInfo, &Field)>) -> TokenStream2 {
+    let mut fallible = false;
     let mut func_stream = TokenStream2::default();
     for (fi, f) in fields.iter() {
         let db_field_ident = format_ident!("{}", fi.column_name.to_uppercase());
@@ -319,17 +348,67 @@ fn to_sql_inserter(fields: &Vec<(FieldInfo, &Field)>) -> TokenStream2 {
         if let Some(mp) = fi.to_sql_func.as_ref() {
             let mp = format_ident!("{}", mp);
             func_stream.extend(quote! { .field(Self::#db_field_ident, #mp(self.#field_indent)) });
+        } else if matches!(fi.field_type, chin_sql::LogicFieldType::Json) {
+            fallible = true;
+            func_stream.extend(quote! {
+                .field(Self::#db_field_ident, chin_sql::SqlValue::try_from(self.#field_indent)?)
+            });
         } else {
             func_stream.extend(quote! { .field(Self::#db_field_ident, self.#field_indent) });
         }
     }
 
-    quote! {
-        pub fn to_sql_inserter(self) -> chin_sql::SqlInserter<'static> {
-            chin_sql::SqlInserter::new(Self::TABLE)
-                #func_stream
+    if fallible {
+        quote! {
+            pub fn try_to_sql_inserter(
+                self,
+            ) -> Result<chin_sql::SqlInserter<'static>, chin_sql::ChinSqlError> {
+                Ok(chin_sql::SqlInserter::new(Self::TABLE)
+                    #func_stream)
+            }
+        }
+    } else {
+        quote! {
+            pub fn to_sql_inserter(self) -> chin_sql::SqlInserter<'static> {
+                chin_sql::SqlInserter::new(Self::TABLE)
+                    #func_stream
+            }
+        }
+    }
+}
+
+fn generate_from_sql_row(
+    struct_name: &syn::Ident,
+    fields: &Vec<&Field>,
+) -> Result<TokenStream2, syn::Error> {
+    let mut field_stream = TokenStream2::default();
+    for f in fields.iter() {
+        let fi = parse_field_info(f)?;
+        let db_field_ident = format_ident!("{}", fi.column_name.to_uppercase());
+        let Some(field_indent) = f.ident.clone() else {
+            return Err(syn::Error::new(f.span(), "this field has no ident"));
+        };
+        if let Some(mp) = fi.from_sql_func.as_ref() {
+            let mp = format_ident!("{}", mp);
+            field_stream.extend(
+                quote! { #field_indent: #mp(row.value(Self::#db_field_ident)?.clone())?, },
+            );
+        } else {
+            field_stream.extend(quote! { #field_indent: row.get(Self::#db_field_ident)?, });
         }
     }
+
+    Ok(quote! {
+        impl chin_sql::FromSqlRow for #struct_name {
+            fn from_sql_row(
+                row: &chin_sql::SqlValueRow,
+            ) -> Result<Self, chin_sql::ChinSqlError> {
+                Ok(Self {
+                    #field_stream
+                })
+            }
+        }
+    })
 }
 
 fn key_func(prefix: &str, fields: &Vec<(&FieldInfo, &Field)>) -> TokenStream2 {
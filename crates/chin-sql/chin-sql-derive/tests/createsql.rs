@@ -1,5 +1,8 @@
+use std::collections::BTreeMap;
+
 use chin_sql::DbType;
-use chin_sql::str_type::Varchar;
+use chin_sql::json_type::Json;
+use chin_sql::str_type::{Text, Varchar};
 use chin_sql_derive::GenerateTableSchema;
 use chrono::DateTime;
 use chrono::FixedOffset;
@@ -17,6 +20,30 @@ struct ExampleTable {
     create_at2: Option<DateTime<FixedOffset>>,
 }
 
+#[allow(dead_code)]
+#[derive(GenerateTableSchema)]
+struct Article {
+    #[gts_primary]
+    id: i64,
+
+    #[gts_fts]
+    title: Text,
+
+    #[gts_fts]
+    body: Option<Text>,
+}
+
+#[allow(dead_code)]
+#[derive(GenerateTableSchema)]
+struct Profile {
+    #[gts_primary]
+    id: i64,
+
+    tags: Json<Vec<String>>,
+
+    extra: Option<Json<BTreeMap<String, i64>>>,
+}
+
 #[test]
 fn table_generate() {
     println!(
@@ -33,3 +60,93 @@ fn table_generate() {
 
     assert_eq!("create_at", ExampleTable::CREATE_AT);
 }
+
+#[test]
+fn decode_row() {
+    use chin_sql::{FromSqlRow, SqlValue, SqlValueRow};
+
+    let row: SqlValueRow = [
+        ("id", SqlValue::Str("1".into())),
+        ("create_at", SqlValue::NullUnknown),
+        ("create_at2", SqlValue::NullUnknown),
+    ]
+    .into_iter()
+    .collect();
+    let example = ExampleTable::from_sql_row(&row).unwrap();
+    assert_eq!("1", example.id.as_str());
+    assert!(example.create_at.is_none());
+
+    let row: SqlValueRow = [("id", SqlValue::Str("1".into()))].into_iter().collect();
+    let err = ExampleTable::from_sql_row(&row).err().unwrap();
+    assert!(err.to_string().contains("`create_at`"));
+}
+
+#[test]
+fn full_text() {
+    use chin_sql::IntoSqlSeg;
+
+    assert_eq!(&["title", "body"], Article::create_sql().fts);
+
+    let sqls = Article::create_sql()
+        .to_owned_sql()
+        .sqls(DbType::Postgres)
+        .unwrap();
+    assert_eq!(
+        "create index if not exists \"article_fts\" on \"article\" using gin \
+         (to_tsvector('simple'::regconfig, coalesce(\"title\", '') || ' ' || coalesce(\"body\", '')))",
+        sqls.last().unwrap()
+    );
+
+    let sqls = Article::create_sql()
+        .to_owned_sql()
+        .sqls(DbType::Sqlite)
+        .unwrap();
+    assert_eq!(5, sqls.len());
+    assert!(sqls[1].starts_with("create virtual table if not exists \"article_fts\" using fts5"));
+
+    assert_eq!(&["title", "body"], Article::full_text().columns());
+
+    // no Json column, so inserting can not fail
+    let inserter: chin_sql::SqlInserter<'static> = Article {
+        id: 1,
+        title: "a".to_owned().into(),
+        body: None,
+    }
+    .to_sql_inserter();
+    assert!(inserter.into_sql_seg(DbType::Sqlite).is_ok());
+}
+
+#[test]
+fn json_column() {
+    use chin_sql::{FromSqlRow, IntoSqlSeg, SqlValue, SqlValueRow};
+
+    let sqls = Profile::create_sql()
+        .to_owned_sql()
+        .sqls(DbType::Postgres)
+        .unwrap();
+    assert!(sqls[0].contains("\"tags\" JSONB not null"));
+    assert!(sqls[0].contains("\"extra\" JSONB"));
+
+    let profile = Profile {
+        id: 1,
+        tags: Json(vec!["a".to_owned()]),
+        extra: None,
+    };
+    let seg = profile
+        .try_to_sql_inserter()
+        .unwrap()
+        .into_sql_seg(DbType::Sqlite)
+        .unwrap();
+    assert!(matches!(&seg.values[1], SqlValue::Json(v) if v.as_str() == r#"["a"]"#));
+
+    let row: SqlValueRow = [
+        ("id", SqlValue::I64(1)),
+        ("tags", SqlValue::Str(r#"["a","b"]"#.into())),
+        ("extra", SqlValue::Str(r#"{"x":1}"#.into())),
+    ]
+    .into_iter()
+    .collect();
+    let profile = Profile::from_sql_row(&row).unwrap();
+    assert_eq!(vec!["a", "b"], *profile.tags);
+    assert_eq!(Some(&1), profile.extra.unwrap().get("x"));
+}
@@ -0,0 +1,199 @@
+use crate::{
+    ChinSqlError, DbType, IntoSqlSeg, LimitOffset, OrderBy, PlaceHolderType, SqlBuilder, SqlReader,
+    SqlSeg,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub enum CompoundOp {
+    Union,
+    UnionAll,
+    Intersect,
+    Except,
+}
+
+impl CompoundOp {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            CompoundOp::Union => "union",
+            CompoundOp::UnionAll => "union all",
+            CompoundOp::Intersect => "intersect",
+            CompoundOp::Except => "except",
+        }
+    }
+}
+
+/// Readers combined with set operators, the optional order by and limit
+/// apply to the whole result.
+///
+/// Members are combined from left to right like SQLite does. Postgres
+/// members are wrapped in parentheses so each of them may carry its own
+/// order by / limit, SQLite does not allow that and refuses it.
+#[derive(Clone, Debug)]
+pub struct CompoundQuery<'a> {
+    first: SqlReader<'a>,
+    parts: Vec<(CompoundOp, SqlReader<'a>)>,
+    order_by: Option<Vec<OrderBy<'a>>>,
+    limit: Option<LimitOffset>,
+}
+
+impl<'a> CompoundQuery<'a> {
+    pub fn new(first: SqlReader<'a>) -> Self {
+        Self {
+            first,
+            parts: vec![],
+            order_by: None,
+            limit: None,
+        }
+    }
+
+    pub fn combine(mut self, op: CompoundOp, reader: SqlReader<'a>) -> Self {
+        self.parts.push((op, reader));
+        self
+    }
+
+    pub fn union(self, reader: SqlReader<'a>) -> Self {
+        self.combine(CompoundOp::Union, reader)
+    }
+
+    pub fn union_all(self, reader: SqlReader<'a>) -> Self {
+        self.combine(CompoundOp::UnionAll, reader)
+    }
+
+    pub fn intersect(self, reader: SqlReader<'a>) -> Self {
+        self.combine(CompoundOp::Intersect, reader)
+    }
+
+    pub fn except(self, reader: SqlReader<'a>) -> Self {
+        self.combine(CompoundOp::Except, reader)
+    }
+
+    pub fn order_by<T: Into<Vec<OrderBy<'a>>>>(mut self, orders: T) -> Self {
+        self.order_by.replace(orders.into());
+        self
+    }
+
+    pub fn limit(mut self, limit: LimitOffset) -> Self {
+        self.limit.replace(limit);
+        self
+    }
+}
+
+impl<'a> IntoSqlSeg<'a> for CompoundQuery<'a> {
+    fn into_sql_seg2(
+        self,
+        db_type: DbType,
+        pht: &mut PlaceHolderType,
+    ) -> Result<SqlSeg<'a>, ChinSqlError> {
+        let CompoundQuery {
+            first,
+            parts,
+            order_by,
+            limit,
+        } = self;
+
+        let dialect = db_type.dialect();
+        let member = |sb: SqlBuilder<'a>, reader: SqlReader<'a>| {
+            if dialect.parenthesize_compound() {
+                Ok(sb.seg("(").merge(reader).seg(")"))
+            } else if reader.order_by.is_some() || reader.limit.is_some() {
+                Err(ChinSqlError::BuilderSqlError(format!(
+                    "{} compound member cannot have order by or limit",
+                    dialect.name()
+                )))
+            } else {
+                Ok(sb.merge(reader))
+            }
+        };
+
+        let mut sb = member(SqlBuilder::new(), first)?;
+        for (id, (op, reader)) in parts.into_iter().enumerate() {
+            // intersect binds tighter than union and except
+            if id > 0 && dialect.parenthesize_compound() && matches!(op, CompoundOp::Intersect) {
+                sb = SqlBuilder::new().seg("(").merge(sb).seg(")");
+            }
+            sb = member(sb.seg(op.as_str()), reader)?;
+        }
+
+        sb.order_by_clause(order_by)
+            .transform(|this| match limit {
+                Some(lo) => this.limit_offset(lo),
+                None => this,
+            })
+            .into_sql_seg2(db_type, pht)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        CompoundQuery, DbType, Froms, IntoSqlSeg, LimitOffset, OrderBy, SqlReader, SqlTypedField,
+        test_util::squash,
+    };
+
+    fn reader<'a>(table_name: &'a str, id: &'a SqlTypedField<'a, i64>) -> SqlReader<'a> {
+        SqlReader::builder(
+            [id.erased()],
+            Froms::Table {
+                table_name,
+                alias: "t",
+            },
+        )
+        .wheres(id.v_gt(1))
+        .build()
+    }
+
+    fn compound<'a>(id: &'a SqlTypedField<'a, i64>) -> CompoundQuery<'a> {
+        CompoundQuery::new(reader("a", id))
+            .union_all(reader("b", id))
+            .except(reader("c", id))
+            .order_by([OrderBy::Asc("id".into())])
+            .limit(LimitOffset::new(10))
+    }
+
+    #[test]
+    fn compound_sqlite() {
+        let id = SqlTypedField::<i64>::new("t", "id");
+        let seg = compound(&id).into_sql_seg(DbType::Sqlite).unwrap();
+        assert_eq!(
+            "select \"t\".\"id\" from \"a\" as \"t\" where \"t\".\"id\" > ? \
+             union all select \"t\".\"id\" from \"b\" as \"t\" where \"t\".\"id\" > ? \
+             except select \"t\".\"id\" from \"c\" as \"t\" where \"t\".\"id\" > ? \
+             order by id asc limit 10",
+            squash(&seg.seg)
+        );
+        assert_eq!(3, seg.values.len());
+
+        let limited = reader("a", &id).limit(LimitOffset::new(1));
+        assert!(
+            CompoundQuery::new(limited)
+                .intersect(reader("b", &id))
+                .into_sql_seg(DbType::Sqlite)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn compound_postgres() {
+        let id = SqlTypedField::<i64>::new("t", "id");
+        let seg = compound(&id).into_sql_seg(DbType::Postgres).unwrap();
+        assert_eq!(
+            "( select \"t\".\"id\" from \"a\" as \"t\" where \"t\".\"id\" > $1 ) \
+             union all ( select \"t\".\"id\" from \"b\" as \"t\" where \"t\".\"id\" > $2 ) \
+             except ( select \"t\".\"id\" from \"c\" as \"t\" where \"t\".\"id\" > $3 ) \
+             order by id asc limit 10",
+            squash(&seg.seg)
+        );
+
+        let seg = CompoundQuery::new(reader("a", &id))
+            .union(reader("b", &id))
+            .intersect(reader("c", &id))
+            .into_sql_seg(DbType::Postgres)
+            .unwrap();
+        assert_eq!(
+            "( ( select \"t\".\"id\" from \"a\" as \"t\" where \"t\".\"id\" > $1 ) \
+             union ( select \"t\".\"id\" from \"b\" as \"t\" where \"t\".\"id\" > $2 ) ) \
+             intersect ( select \"t\".\"id\" from \"c\" as \"t\" where \"t\".\"id\" > $3 )",
+            squash(&seg.seg)
+        );
+    }
+}
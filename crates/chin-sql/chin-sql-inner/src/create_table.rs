@@ -1,4 +1,4 @@
-use crate::{IntoSqlSeg, LogicFieldType, SqlBuilder};
+use crate::{ChinSqlError, Ident, IntoSqlSeg, LogicFieldType, SqlBuilder};
 
 #[derive(Clone, Debug)]
 pub struct CreateTableField {
@@ -14,6 +14,9 @@ pub struct CreateTableSql {
     pub pkey: &'static [&'static str],
     pub unikeys: &'static [(&'static str, &'static [&'static str])],
     pub keys: &'static [(&'static str, &'static [&'static str])],
+    /// `Text` columns searched with [`FullText`](crate::FullText), empty
+    /// when the table has no full-text index.
+    pub fts: &'static [&'static str],
 }
 
 impl CreateTableSql {
@@ -28,39 +31,7 @@ pub struct CreateTableSqlOwned {
     pub pkey: Vec<String>,
     pub unikeys: Vec<(String, Vec<String>)>,
     pub keys: Vec<(String, Vec<String>)>,
-}
-
-impl LogicFieldType {
-    fn to_type(self, db_type: crate::DbType) -> String {
-        match db_type {
-            crate::DbType::Sqlite => match self {
-                LogicFieldType::Bool => "INTEGER".into(),
-                LogicFieldType::I8 => "INTEGER".into(),
-                LogicFieldType::I16 => "INTEGER".into(),
-                LogicFieldType::I32 => "INTEGER".into(),
-                LogicFieldType::I64 => "INTEGER".into(),
-                LogicFieldType::F64 => "REAL".into(),
-                LogicFieldType::Varchar(_) => "TEXT".into(),
-                LogicFieldType::Text => "TEXT".into(),
-                LogicFieldType::Blob => "BLOB".into(),
-                LogicFieldType::Timestamptz => "INTEGER".into(),
-                LogicFieldType::Timestamp => "INTEGER".into(),
-            },
-            crate::DbType::Postgres => match self {
-                LogicFieldType::Bool => "BOOL".into(),
-                LogicFieldType::I8 => "CHAR".into(),
-                LogicFieldType::I16 => "INT2".into(),
-                LogicFieldType::I32 => "INT4".into(),
-                LogicFieldType::I64 => "INT8".into(),
-                LogicFieldType::F64 => "FLOAT8".into(),
-                LogicFieldType::Varchar(len) => format!("Varchar({len})"),
-                LogicFieldType::Text => "TEXT".into(),
-                LogicFieldType::Blob => "BLOB".into(),
-                LogicFieldType::Timestamptz => "TIMESTAMPTZ".into(),
-                LogicFieldType::Timestamp => "TIMESTAMP".into(),
-            },
-        }
-    }
+    pub fts: Vec<String>,
 }
 
 impl CreateTableSql {
@@ -79,64 +50,78 @@ impl CreateTableSql {
                 .iter()
                 .map(|(k, v)| (k.to_string(), v.iter().map(|e| e.to_string()).collect()))
                 .collect(),
+            fts: self.fts.iter().map(|e| e.to_string()).collect(),
         }
     }
 }
 
 impl CreateTableSqlOwned {
     pub fn sqls(self, db_type: crate::DbType) -> Result<Vec<String>, crate::ChinSqlError> {
+        let quote = |name: &str| Ident::new(name).quote(db_type);
+        let quote_all = |names: &[String]| {
+            names
+                .iter()
+                .map(|e| quote(e))
+                .collect::<Result<Vec<String>, ChinSqlError>>()
+                .map(|e| e.join(","))
+        };
+        let table_name = quote(&self.table_name)?;
+
         let mut sr = SqlBuilder::new()
             .seg("create table if not exists")
-            .seg(self.table_name.clone())
+            .seg(table_name.clone())
             .seg("(");
 
-        let columns: Vec<String> = self
+        let columns = self
             .fields
             .iter()
             .map(|f| {
-                format!(
+                Ok(format!(
                     "{} {} {}",
-                    f.name,
-                    f.kind.to_type(db_type),
+                    quote(f.name)?,
+                    db_type.dialect().column_type(f.kind),
                     if f.not_null { "not null" } else { "" }
-                )
+                ))
             })
-            .collect();
+            .collect::<Result<Vec<String>, ChinSqlError>>()?;
         sr = sr.seg(columns.join(", "));
         if !self.pkey.is_empty() {
             sr = sr
                 .seg(", ")
                 .seg("primary key (")
-                .seg(self.pkey.join(","))
+                .seg(quote_all(&self.pkey)?)
                 .seg(")");
         }
         sr = sr.seg(")");
 
         let mut result = vec![];
         let ct = sr
-            .into_sql_seg2(db_type, &mut crate::PlaceHolderType::QustionMark)?
+            .into_sql_seg2(db_type, &mut crate::PlaceHolderType::question_mark())?
             .seg;
         result.push(ct);
         for (key, fields) in self.unikeys {
-            result.push(format!(
-                "create unique index if not exists {}_{} on {}({})",
-                self.table_name,
-                key,
-                self.table_name,
-                fields.join(",")
+            result.push(db_type.dialect().create_index(
+                true,
+                &quote(&format!("{}_{}", self.table_name, key))?,
+                &table_name,
+                &quote_all(&fields)?,
             ));
         }
 
         for (key, fields) in self.keys {
-            result.push(format!(
-                "create index if not exists {}_{} on {}({})",
-                self.table_name,
-                key,
-                self.table_name,
-                fields.join(",")
+            result.push(db_type.dialect().create_index(
+                false,
+                &quote(&format!("{}_{}", self.table_name, key))?,
+                &table_name,
+                &quote_all(&fields)?,
             ));
         }
 
+        if !self.fts.is_empty() {
+            let fts: Vec<&str> = self.fts.iter().map(String::as_str).collect();
+            result.extend(db_type.dialect().create_full_text(&self.table_name, &fts)?);
+        }
+
         Ok(result)
     }
 }
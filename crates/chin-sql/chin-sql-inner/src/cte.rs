@@ -0,0 +1,193 @@
+use crate::{CompoundOp, Froms, SqlBuilder, SqlReader};
+
+/// A named query in a `with` clause, the recursive part of a recursive
+/// CTE is attached to the anchor with [`Cte::union_all`] or [`Cte::union`].
+pub struct Cte<'a> {
+    name: &'a str,
+    columns: Vec<&'a str>,
+    anchor: SqlReader<'a>,
+    parts: Vec<(CompoundOp, SqlReader<'a>)>,
+}
+
+impl<'a> Cte<'a> {
+    pub fn new(name: &'a str, anchor: SqlReader<'a>) -> Self {
+        Self {
+            name,
+            columns: vec![],
+            anchor,
+            parts: vec![],
+        }
+    }
+
+    pub fn columns<T: Into<Vec<&'a str>>>(mut self, columns: T) -> Self {
+        self.columns = columns.into();
+        self
+    }
+
+    pub fn union_all(mut self, reader: SqlReader<'a>) -> Self {
+        self.parts.push((CompoundOp::UnionAll, reader));
+        self
+    }
+
+    pub fn union(mut self, reader: SqlReader<'a>) -> Self {
+        self.parts.push((CompoundOp::Union, reader));
+        self
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Reference this CTE in a `from` or `join` clause.
+    pub fn table(&self, alias: &'a str) -> Froms<'a> {
+        Froms::Table {
+            table_name: self.name,
+            alias,
+        }
+    }
+}
+
+impl<'a> From<Cte<'a>> for SqlBuilder<'a> {
+    fn from(value: Cte<'a>) -> Self {
+        let Cte {
+            name,
+            columns,
+            anchor,
+            parts,
+        } = value;
+
+        let mut sb = SqlBuilder::new().ident(name);
+        if !columns.is_empty() {
+            sb = sb.seg("(").idents(columns).seg(")");
+        }
+        sb = sb.seg("as (").merge(anchor);
+        for (op, reader) in parts {
+            sb = sb.seg(op.as_str()).merge(reader);
+        }
+        sb.seg(")")
+    }
+}
+
+pub struct WithQuery<'a> {
+    recursive: bool,
+    ctes: Vec<Cte<'a>>,
+}
+
+impl<'a> Default for WithQuery<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> WithQuery<'a> {
+    pub fn new() -> Self {
+        Self {
+            recursive: false,
+            ctes: vec![],
+        }
+    }
+
+    pub fn recursive(mut self) -> Self {
+        self.recursive = true;
+        self
+    }
+
+    pub fn cte(mut self, cte: Cte<'a>) -> Self {
+        self.ctes.push(cte);
+        self
+    }
+
+    pub fn query<T: Into<SqlBuilder<'a>>>(self, query: T) -> SqlBuilder<'a> {
+        let mut sb = SqlBuilder::new();
+        if !self.ctes.is_empty() {
+            sb = sb.seg(if self.recursive {
+                "with recursive"
+            } else {
+                "with"
+            });
+            let len = self.ctes.len();
+            for (id, cte) in self.ctes.into_iter().enumerate() {
+                sb = sb.merge(cte);
+                if id < len - 1 {
+                    sb = sb.seg(",");
+                }
+            }
+        }
+        sb.merge(query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Cte, DbType, Froms, IntoSqlSeg, JoinTable, JoinType, Joins, SqlReader, SqlTypedField,
+        WithQuery, test_util::squash,
+    };
+
+    #[test]
+    fn recursive_tree() {
+        let id = SqlTypedField::<i64>::new("n", "id");
+        let parent_id = SqlTypedField::<i64>::new("n", "parent_id");
+        let tree_id = SqlTypedField::<i64>::new("t", "id");
+
+        let anchor = SqlReader::builder(
+            [id.erased(), parent_id.erased()],
+            Froms::Table {
+                table_name: "node",
+                alias: "n",
+            },
+        )
+        .wheres(id.v_eq(1))
+        .build();
+        let step = SqlReader::builder(
+            [id.erased(), parent_id.erased()],
+            Joins::new(Froms::Table {
+                table_name: "node",
+                alias: "n",
+            })
+            .join(JoinTable {
+                join_type: JoinType::InnerJoin,
+                table: Froms::Table {
+                    table_name: "tree",
+                    alias: "t",
+                },
+                conds: vec![
+                    (
+                        SqlTypedField::<i64>::new("n", "parent_id"),
+                        SqlTypedField::<i64>::new("t", "id"),
+                    )
+                        .into(),
+                ],
+            })
+            .into(),
+        )
+        .wheres(parent_id.v_lt(100))
+        .build();
+        let tree = Cte::new("tree", anchor)
+            .columns(["id", "parent_id"])
+            .union_all(step);
+        let from = tree.table("t");
+
+        let seg = WithQuery::new()
+            .recursive()
+            .cte(tree)
+            .query(
+                SqlReader::builder([tree_id.erased()], from)
+                    .wheres(tree_id.v_gt(0))
+                    .build(),
+            )
+            .into_sql_seg(DbType::Postgres)
+            .unwrap();
+
+        assert_eq!(
+            "with recursive \"tree\" ( \"id\", \"parent_id\" ) as ( \
+             select \"n\".\"id\", \"n\".\"parent_id\" from \"node\" as \"n\" where \"n\".\"id\" = $1 \
+             union all \
+             select \"n\".\"id\", \"n\".\"parent_id\" from \"node\" as \"n\" inner join \"tree\" as \"t\" on \"n\".\"parent_id\" = \"t\".\"id\" \
+             where \"n\".\"parent_id\" < $2 ) \
+             select \"t\".\"id\" from \"tree\" as \"t\" where \"t\".\"id\" > $3",
+            squash(&seg.seg)
+        );
+        assert_eq!(3, seg.values.len());
+    }
+}
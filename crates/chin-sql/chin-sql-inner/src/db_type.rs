@@ -1,7 +1,28 @@
+use crate::{Dialect, MySql, Postgres, Sqlite};
+
 #[derive(Clone, Copy)]
 pub enum DbType {
     Sqlite,
     Postgres,
+    MySql,
+    /// A dialect implemented outside this crate.
+    Custom(&'static dyn Dialect),
+}
+
+impl DbType {
+    pub fn dialect(self) -> &'static dyn Dialect {
+        match self {
+            DbType::Sqlite => &Sqlite,
+            DbType::Postgres => &Postgres,
+            DbType::MySql => &MySql,
+            DbType::Custom(dialect) => dialect,
+        }
+    }
+
+    /// The most bound parameters a single statement may use.
+    pub fn max_params(&self) -> usize {
+        self.dialect().max_params()
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -17,4 +38,5 @@ pub enum LogicFieldType {
     Blob,
     Timestamptz,
     Timestamp,
+    Json,
 }
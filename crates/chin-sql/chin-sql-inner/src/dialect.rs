@@ -0,0 +1,1105 @@
+use chrono::{DateTime, FixedOffset, SecondsFormat};
+
+use crate::{
+    ChinSqlError, FullText, FullTextSql, Ident, LogicFieldType, OnConflict, PlaceHolderType,
+    SqlArray, SqlValue, sql_value::sqlite::sqltype::Timestamptz,
+};
+
+/// Postgres silently truncates longer identifiers.
+const POSTGRES_MAX_IDENT_LEN: usize = 63;
+
+const MYSQL_MAX_IDENT_LEN: usize = 64;
+
+/// The text search configuration of Postgres full-text search, `simple` does
+/// no stemming so it works for any language.
+const TS_CONFIG: &str = "'simple'::regconfig";
+
+/// Everything the builders render differently per database. Only the parts
+/// that differ have to be written, the provided methods follow Postgres and
+/// SQLite where both agree. Use [`DbType::Custom`](crate::DbType::Custom)
+/// to render with a dialect defined outside this crate.
+pub trait Dialect: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// A fresh placeholder source for one statement.
+    fn placeholder(&self) -> PlaceHolderType;
+
+    /// The most bound parameters a single statement may use.
+    fn max_params(&self) -> usize;
+
+    /// `name` is neither empty nor containing nul, see [`Ident::quote`].
+    fn quote_ident(&self, name: &str) -> Result<String, ChinSqlError> {
+        Ok(Ident::new(name).quoted())
+    }
+
+    fn column_type(&self, kind: LogicFieldType) -> String;
+
+    /// The word between `insert` and `into` which handles the conflict on
+    /// its own, no upsert clause is rendered then.
+    fn insert_modifier(&self, _on_conflict: &OnConflict<'_>) -> Option<&'static str> {
+        None
+    }
+
+    /// The clause after the values, `target` and `set` are quoted columns.
+    /// An empty `target` means any conflict, an empty `set` means do nothing.
+    fn upsert(&self, target: &[String], set: &[String]) -> Result<String, ChinSqlError> {
+        let mut sql = String::from(" on conflict");
+        if !target.is_empty() {
+            sql.push_str(&format!(" ({})", target.join(", ")));
+        }
+        if set.is_empty() {
+            sql.push_str(" do nothing");
+        } else {
+            let setters: Vec<String> = set
+                .iter()
+                .map(|column| format!("{column} = excluded.{column}"))
+                .collect();
+            sql.push_str(" do update set ");
+            sql.push_str(&setters.join(", "));
+        }
+        Ok(sql)
+    }
+
+    /// Whether the upsert clause takes a `where` filter.
+    fn upsert_filter(&self) -> bool {
+        true
+    }
+
+    /// Whether `returning` is supported on insert, update and delete.
+    fn returning(&self) -> bool {
+        true
+    }
+
+    /// `key` matches the pattern bound to `ph` ignoring case, `escape` is
+    /// the pattern's escape character.
+    fn ilike(&self, key: &str, ph: &str, escape: char) -> String;
+
+    /// [`Self::ilike`] ignoring case beyond ASCII, see [`Wheres::unicode_ilike`].
+    ///
+    /// [`Wheres::unicode_ilike`]: crate::Wheres::unicode_ilike
+    fn unicode_ilike(&self, key: &str, ph: &str, escape: char) -> String {
+        self.ilike(key, ph, escape)
+    }
+
+    /// `key` matches any element of the array bound to `ph`.
+    fn array_predicate(&self, key: &str, ph: &str) -> String;
+
+    fn limit(&self, limit: usize, offset: Option<usize>) -> String {
+        match offset {
+            Some(offset) => format!("limit {limit} offset {offset}"),
+            None => format!("limit {limit}"),
+        }
+    }
+
+    /// Whether compound members are parenthesized, which also makes
+    /// `intersect` bind tighter than `union` and `except`. Without it
+    /// members cannot have their own order by or limit.
+    fn parenthesize_compound(&self) -> bool {
+        true
+    }
+
+    /// Whether `update ... from` lists the joined tables after the updated
+    /// one, as in `update t, u set ...`.
+    fn update_from_in_table_list(&self) -> bool {
+        false
+    }
+
+    /// What follows `delete from {table}` to delete with joined tables,
+    /// `filter` is empty or ` where ...`.
+    fn delete_using(&self, _table: &str, using: &str, filter: &str) -> String {
+        format!(" using {using}{filter}")
+    }
+
+    fn create_index(&self, unique: bool, name: &str, table: &str, columns: &str) -> String {
+        let unique = if unique { "unique " } else { "" };
+        format!("create {unique}index if not exists {name} on {table}({columns})")
+    }
+
+    /// The parameter bound for the user's search text.
+    fn full_text_query(&self, text: &str) -> String {
+        text.to_owned()
+    }
+
+    /// The row matches the search text bound to `ph`.
+    fn full_text_match(&self, _index: &FullTextSql, _ph: &str) -> Result<String, ChinSqlError> {
+        Err(self.unsupported("full-text search"))
+    }
+
+    /// The score of the row for the search text bound to `ph`, higher is
+    /// better.
+    fn full_text_rank(&self, _index: &FullTextSql, _ph: &str) -> Result<String, ChinSqlError> {
+        Err(self.unsupported("full-text search"))
+    }
+
+    /// Statements creating the full-text index on `columns` of `table`, see
+    /// [`FullText::fts_table`] for its name.
+    fn create_full_text(
+        &self,
+        _table: &str,
+        _columns: &[&str],
+    ) -> Result<Vec<String>, ChinSqlError> {
+        Err(self.unsupported("full-text search"))
+    }
+
+    /// The scalar at `path` inside the JSON column `key`, elements made of
+    /// digits index arrays. It is compared with `value`, so it has to have
+    /// the same type.
+    fn json_extract(
+        &self,
+        _key: &str,
+        _path: &[&str],
+        _value: &SqlValue<'_>,
+    ) -> Result<String, ChinSqlError> {
+        Err(self.unsupported("json path"))
+    }
+
+    /// The JSON column `key` contains the JSON document bound to `ph`.
+    fn json_contains(&self, _key: &str, _ph: &str) -> Result<String, ChinSqlError> {
+        Err(self.unsupported("json containment"))
+    }
+
+    fn unsupported(&self, feature: &str) -> ChinSqlError {
+        ChinSqlError::BuilderSqlError(format!("{} does not support {feature}", self.name()))
+    }
+
+    fn bool_literal(&self, v: bool) -> String {
+        v.to_string()
+    }
+
+    fn float_literal(&self, v: f64) -> String {
+        if v.is_finite() {
+            format!("{v:?}")
+        } else {
+            "null".to_owned()
+        }
+    }
+
+    fn str_literal(&self, v: &str) -> String {
+        format!("'{}'", v.replace('\'', "''"))
+    }
+
+    fn blob_literal(&self, v: &[u8]) -> String {
+        format!("x'{}'", hex(v))
+    }
+
+    fn time_literal(&self, v: &DateTime<FixedOffset>) -> String;
+
+    fn array_literal(&self, v: &SqlArray) -> String {
+        self.str_literal(&v.to_json())
+    }
+
+    fn json_literal(&self, v: &str) -> String {
+        self.str_literal(v)
+    }
+
+    /// The value as a SQL literal, encoded the same way it is bound.
+    fn literal(&self, value: &SqlValue<'_>) -> String {
+        match value {
+            SqlValue::Bool(v) => self.bool_literal(*v),
+            SqlValue::I8(v) => v.to_string(),
+            SqlValue::I16(v) => v.to_string(),
+            SqlValue::I32(v) => v.to_string(),
+            SqlValue::I64(v) => v.to_string(),
+            SqlValue::F64(v) => self.float_literal(*v),
+            SqlValue::Str(v) => self.str_literal(v),
+            SqlValue::FixedOffset(v) => self.time_literal(v),
+            SqlValue::Utc(v) => self.time_literal(&v.fixed_offset()),
+            SqlValue::Blob(v) => self.blob_literal(v),
+            SqlValue::Array(v) => self.array_literal(v),
+            SqlValue::Json(v) => self.json_literal(v.as_str()),
+            SqlValue::Null(_) | SqlValue::NullUnknown => "null".to_owned(),
+        }
+    }
+}
+
+pub struct Sqlite;
+
+pub struct Postgres;
+
+/// MySQL 8 and MariaDB. Identifiers are double quoted like everywhere else,
+/// so the connection needs `ANSI_QUOTES` in its `sql_mode`.
+pub struct MySql;
+
+impl Dialect for Sqlite {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn placeholder(&self) -> PlaceHolderType {
+        PlaceHolderType::question_mark()
+    }
+
+    /// SQLite has allowed 32766 since 3.32.
+    fn max_params(&self) -> usize {
+        32766
+    }
+
+    fn column_type(&self, kind: LogicFieldType) -> String {
+        match kind {
+            LogicFieldType::Bool => "INTEGER".into(),
+            LogicFieldType::I8 => "INTEGER".into(),
+            LogicFieldType::I16 => "INTEGER".into(),
+            LogicFieldType::I32 => "INTEGER".into(),
+            LogicFieldType::I64 => "INTEGER".into(),
+            LogicFieldType::F64 => "REAL".into(),
+            LogicFieldType::Varchar(_) => "TEXT".into(),
+            LogicFieldType::Text => "TEXT".into(),
+            LogicFieldType::Blob => "BLOB".into(),
+            LogicFieldType::Timestamptz => "INTEGER".into(),
+            LogicFieldType::Timestamp => "INTEGER".into(),
+            LogicFieldType::Json => "TEXT".into(),
+        }
+    }
+
+    fn insert_modifier(&self, on_conflict: &OnConflict<'_>) -> Option<&'static str> {
+        match on_conflict {
+            OnConflict::Ignore => Some("or ignore"),
+            OnConflict::Replace(_) => Some("or replace"),
+            _ => None,
+        }
+    }
+
+    /// `like` ignores the case of ASCII letters already.
+    fn ilike(&self, key: &str, ph: &str, escape: char) -> String {
+        format!("{key} like {ph} escape '{escape}'")
+    }
+
+    /// Both sides are lowered by `unicode_lower`, which is not built in.
+    /// actor-sqlite registers it on its connections, others fail with
+    /// "no such function".
+    fn unicode_ilike(&self, key: &str, ph: &str, escape: char) -> String {
+        format!("unicode_lower({key}) like unicode_lower({ph}) escape '{escape}'")
+    }
+
+    fn array_predicate(&self, key: &str, ph: &str) -> String {
+        format!("{key} in (select value from json_each({ph}))")
+    }
+
+    /// `json_extract` already returns SQL values of the JSON types.
+    fn json_extract(
+        &self,
+        key: &str,
+        path: &[&str],
+        _value: &SqlValue<'_>,
+    ) -> Result<String, ChinSqlError> {
+        Ok(format!(
+            "json_extract({key}, {})",
+            self.str_literal(&json_path(self, path)?)
+        ))
+    }
+
+    fn parenthesize_compound(&self) -> bool {
+        false
+    }
+
+    /// No `delete ... using`, the joined rows are picked by rowid instead.
+    /// `WITHOUT ROWID` tables have no rowid and fail to prepare, delete
+    /// from them with an `in (select pk ...)` filter.
+    fn delete_using(&self, table: &str, using: &str, filter: &str) -> String {
+        format!(" where rowid in (select {table}.rowid from {table}, {using}{filter})")
+    }
+
+    /// Every word is quoted so FTS5 query syntax in the input is not
+    /// interpreted, the words are implicitly and-ed.
+    fn full_text_query(&self, text: &str) -> String {
+        text.split_whitespace()
+            .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    fn full_text_match(&self, index: &FullTextSql, ph: &str) -> Result<String, ChinSqlError> {
+        let FullTextSql {
+            fts_table,
+            reference,
+            ..
+        } = index;
+        Ok(format!(
+            "{reference}.rowid in (select rowid from {fts_table} where {fts_table} match {ph})"
+        ))
+    }
+
+    /// `bm25` is negative with the best match lowest, so it is negated.
+    fn full_text_rank(&self, index: &FullTextSql, ph: &str) -> Result<String, ChinSqlError> {
+        let FullTextSql {
+            fts_table,
+            reference,
+            ..
+        } = index;
+        Ok(format!(
+            "(select -bm25({fts_table}) from {fts_table} \
+             where {fts_table} match {ph} and rowid = {reference}.rowid)"
+        ))
+    }
+
+    /// An external content FTS5 table kept in sync by triggers.
+    fn create_full_text(&self, table: &str, columns: &[&str]) -> Result<Vec<String>, ChinSqlError> {
+        let fts_table = FullText::fts_table(table);
+        let trigger = |suffix: &str| self.quote_ident(&format!("{fts_table}_{suffix}"));
+        let columns = quote_all(self, columns)?;
+        let row = |prefix: &str| {
+            columns
+                .iter()
+                .map(|column| format!("{prefix}.{column}"))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        let list = columns.join(", ");
+        let content = table.replace('\'', "''");
+        let table = self.quote_ident(table)?;
+        let fts_table = self.quote_ident(&fts_table)?;
+        let insert = format!(
+            "insert into {fts_table}(rowid, {list}) values (new.rowid, {});",
+            row("new")
+        );
+        let delete = format!(
+            "insert into {fts_table}({fts_table}, rowid, {list}) values ('delete', old.rowid, {});",
+            row("old")
+        );
+
+        Ok(vec![
+            format!(
+                "create virtual table if not exists {fts_table} using fts5({list}, content='{content}')"
+            ),
+            format!(
+                "create trigger if not exists {} after insert on {table} begin {insert} end",
+                trigger("ai")?
+            ),
+            format!(
+                "create trigger if not exists {} after delete on {table} begin {delete} end",
+                trigger("ad")?
+            ),
+            format!(
+                "create trigger if not exists {} after update on {table} begin {delete} {insert} end",
+                trigger("au")?
+            ),
+        ])
+    }
+
+    fn bool_literal(&self, v: bool) -> String {
+        i32::from(v).to_string()
+    }
+
+    fn float_literal(&self, v: f64) -> String {
+        match v {
+            v if v.is_finite() => format!("{v:?}"),
+            v if v.is_nan() => "null".to_owned(),
+            v => format!("{}9e999", if v < 0.0 { "-" } else { "" }),
+        }
+    }
+
+    /// Timestamps are stored as encoded integers, the readable time is
+    /// added as a comment.
+    fn time_literal(&self, v: &DateTime<FixedOffset>) -> String {
+        format!("{} /* {} */", i64::from(Timestamptz::from(*v)), rfc3339(v))
+    }
+}
+
+impl Dialect for Postgres {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    fn placeholder(&self) -> PlaceHolderType {
+        PlaceHolderType::dollar_number()
+    }
+
+    fn max_params(&self) -> usize {
+        65535
+    }
+
+    fn quote_ident(&self, name: &str) -> Result<String, ChinSqlError> {
+        if name.len() > POSTGRES_MAX_IDENT_LEN {
+            return Err(ChinSqlError::InvalidIdentifier {
+                ident: name.to_owned(),
+                reason: format!("longer than {POSTGRES_MAX_IDENT_LEN} bytes"),
+            });
+        }
+        Ok(Ident::new(name).quoted())
+    }
+
+    fn column_type(&self, kind: LogicFieldType) -> String {
+        match kind {
+            LogicFieldType::Bool => "BOOL".into(),
+            LogicFieldType::I8 => "CHAR".into(),
+            LogicFieldType::I16 => "INT2".into(),
+            LogicFieldType::I32 => "INT4".into(),
+            LogicFieldType::I64 => "INT8".into(),
+            LogicFieldType::F64 => "FLOAT8".into(),
+            LogicFieldType::Varchar(len) => format!("Varchar({len})"),
+            LogicFieldType::Text => "TEXT".into(),
+            LogicFieldType::Blob => "BLOB".into(),
+            LogicFieldType::Timestamptz => "TIMESTAMPTZ".into(),
+            LogicFieldType::Timestamp => "TIMESTAMP".into(),
+            LogicFieldType::Json => "JSONB".into(),
+        }
+    }
+
+    fn ilike(&self, key: &str, ph: &str, escape: char) -> String {
+        format!("{key} ilike {ph} escape '{escape}'")
+    }
+
+    fn array_predicate(&self, key: &str, ph: &str) -> String {
+        format!("{key} = any({ph})")
+    }
+
+    /// `->>` extracts text, which is cast to the type of `value`. A
+    /// [`SqlValue::Json`] is compared as `jsonb` instead.
+    fn json_extract(
+        &self,
+        key: &str,
+        path: &[&str],
+        value: &SqlValue<'_>,
+    ) -> Result<String, ChinSqlError> {
+        let cast = match value {
+            SqlValue::Json(_) => {
+                let elements: Vec<String> = path
+                    .iter()
+                    .map(|e| format!("\"{}\"", e.replace('\\', "\\\\").replace('"', "\\\"")))
+                    .collect();
+                return Ok(format!(
+                    "{key} #> {}",
+                    self.str_literal(&format!("{{{}}}", elements.join(",")))
+                ));
+            }
+            SqlValue::Str(_) | SqlValue::Null(_) | SqlValue::NullUnknown => None,
+            SqlValue::Bool(_) => Some("bool"),
+            SqlValue::I16(_) => Some("int2"),
+            SqlValue::I32(_) => Some("int4"),
+            SqlValue::I64(_) => Some("int8"),
+            SqlValue::F64(_) => Some("float8"),
+            SqlValue::FixedOffset(_) | SqlValue::Utc(_) => Some("timestamptz"),
+            other => {
+                return Err(
+                    self.unsupported(&format!("comparing a json path with {}", other.type_name()))
+                );
+            }
+        };
+        let step = |element: &str| {
+            if is_json_index(element) {
+                element.to_owned()
+            } else {
+                self.str_literal(element)
+            }
+        };
+        let sql = match path.split_last() {
+            Some((last, init)) => {
+                let mut sql = key.to_owned();
+                for element in init {
+                    sql.push_str(&format!("->{}", step(element)));
+                }
+                sql.push_str(&format!("->>{}", step(last)));
+                sql
+            }
+            None => format!("{key} #>> '{{}}'"),
+        };
+        Ok(match cast {
+            Some(cast) => format!("({sql})::{cast}"),
+            None => sql,
+        })
+    }
+
+    fn json_contains(&self, key: &str, ph: &str) -> Result<String, ChinSqlError> {
+        Ok(format!("{key} @> {ph}"))
+    }
+
+    fn full_text_match(&self, index: &FullTextSql, ph: &str) -> Result<String, ChinSqlError> {
+        Ok(format!(
+            "{} @@ plainto_tsquery({TS_CONFIG}, {ph})",
+            tsvector(&index.columns)
+        ))
+    }
+
+    fn full_text_rank(&self, index: &FullTextSql, ph: &str) -> Result<String, ChinSqlError> {
+        Ok(format!(
+            "ts_rank({}, plainto_tsquery({TS_CONFIG}, {ph}))",
+            tsvector(&index.columns)
+        ))
+    }
+
+    /// A gin index on the same `tsvector` the predicates compute.
+    fn create_full_text(&self, table: &str, columns: &[&str]) -> Result<Vec<String>, ChinSqlError> {
+        Ok(vec![format!(
+            "create index if not exists {} on {} using gin ({})",
+            self.quote_ident(&FullText::fts_table(table))?,
+            self.quote_ident(table)?,
+            tsvector(&quote_all(self, columns)?)
+        )])
+    }
+
+    fn float_literal(&self, v: f64) -> String {
+        if v.is_finite() {
+            format!("{v:?}")
+        } else {
+            format!("'{v}'::float8").replace("inf", "Infinity")
+        }
+    }
+
+    fn blob_literal(&self, v: &[u8]) -> String {
+        format!("'\\x{}'::bytea", hex(v))
+    }
+
+    fn time_literal(&self, v: &DateTime<FixedOffset>) -> String {
+        format!("'{}'::timestamptz", rfc3339(v))
+    }
+
+    fn json_literal(&self, v: &str) -> String {
+        format!("{}::jsonb", self.str_literal(v))
+    }
+
+    fn array_literal(&self, v: &SqlArray) -> String {
+        fn join<T, F: Fn(&T) -> String>(vs: &[T], f: F, ty: &str) -> String {
+            let items: Vec<String> = vs.iter().map(f).collect();
+            format!("array[{}]::{ty}[]", items.join(", "))
+        }
+
+        match v {
+            SqlArray::Bool(v) => join(v, |e| self.bool_literal(*e), "bool"),
+            SqlArray::I16(v) => join(v, |e| e.to_string(), "int2"),
+            SqlArray::I32(v) => join(v, |e| e.to_string(), "int4"),
+            SqlArray::I64(v) => join(v, |e| e.to_string(), "int8"),
+            SqlArray::F64(v) => join(v, |e| self.float_literal(*e), "float8"),
+            SqlArray::Str(v) => join(v, |e| self.str_literal(e), "text"),
+            SqlArray::FixedOffset(v) => join(v, |e| self.time_literal(e), "timestamptz"),
+            SqlArray::Utc(v) => join(v, |e| self.time_literal(&e.fixed_offset()), "timestamptz"),
+        }
+    }
+}
+
+impl Dialect for MySql {
+    fn name(&self) -> &'static str {
+        "mysql"
+    }
+
+    /// Named parameters are repeated as `?` since MySQL has no named
+    /// placeholders.
+    fn placeholder(&self) -> PlaceHolderType {
+        PlaceHolderType::positional()
+    }
+
+    fn max_params(&self) -> usize {
+        65535
+    }
+
+    fn quote_ident(&self, name: &str) -> Result<String, ChinSqlError> {
+        if name.chars().count() > MYSQL_MAX_IDENT_LEN {
+            return Err(ChinSqlError::InvalidIdentifier {
+                ident: name.to_owned(),
+                reason: format!("longer than {MYSQL_MAX_IDENT_LEN} characters"),
+            });
+        }
+        Ok(Ident::new(name).quoted())
+    }
+
+    fn column_type(&self, kind: LogicFieldType) -> String {
+        match kind {
+            LogicFieldType::Bool => "BOOLEAN".into(),
+            LogicFieldType::I8 => "TINYINT".into(),
+            LogicFieldType::I16 => "SMALLINT".into(),
+            LogicFieldType::I32 => "INT".into(),
+            LogicFieldType::I64 => "BIGINT".into(),
+            LogicFieldType::F64 => "DOUBLE".into(),
+            LogicFieldType::Varchar(len) => format!("VARCHAR({len})"),
+            LogicFieldType::Text => "TEXT".into(),
+            LogicFieldType::Blob => "BLOB".into(),
+            LogicFieldType::Timestamptz => "TIMESTAMP(6)".into(),
+            LogicFieldType::Timestamp => "DATETIME(6)".into(),
+            LogicFieldType::Json => "JSON".into(),
+        }
+    }
+
+    /// `insert ignore` stands in for every do nothing, MySQL cannot pick
+    /// the conflicting key.
+    fn insert_modifier(&self, on_conflict: &OnConflict<'_>) -> Option<&'static str> {
+        match on_conflict {
+            OnConflict::Ignore | OnConflict::Nothing { .. } => Some("ignore"),
+            _ => None,
+        }
+    }
+
+    fn upsert(&self, _target: &[String], set: &[String]) -> Result<String, ChinSqlError> {
+        if set.is_empty() {
+            return Err(ChinSqlError::InvalidUpsert("no column to update"));
+        }
+        let setters: Vec<String> = set
+            .iter()
+            .map(|column| format!("{column} = values({column})"))
+            .collect();
+        Ok(format!(" on duplicate key update {}", setters.join(", ")))
+    }
+
+    fn upsert_filter(&self) -> bool {
+        false
+    }
+
+    fn returning(&self) -> bool {
+        false
+    }
+
+    /// The default collations are case-insensitive already.
+    fn ilike(&self, key: &str, ph: &str, escape: char) -> String {
+        format!("{key} like {ph} escape '{escape}'")
+    }
+
+    fn array_predicate(&self, key: &str, ph: &str) -> String {
+        format!("{key} member of (cast({ph} as json))")
+    }
+
+    /// `->>` extracts text, MySQL converts it when compared with a number.
+    fn json_extract(
+        &self,
+        key: &str,
+        path: &[&str],
+        _value: &SqlValue<'_>,
+    ) -> Result<String, ChinSqlError> {
+        Ok(format!(
+            "{key}->>{}",
+            self.str_literal(&json_path(self, path)?)
+        ))
+    }
+
+    fn json_contains(&self, key: &str, ph: &str) -> Result<String, ChinSqlError> {
+        Ok(format!("json_contains({key}, {ph})"))
+    }
+
+    fn update_from_in_table_list(&self) -> bool {
+        true
+    }
+
+    fn full_text_match(&self, index: &FullTextSql, ph: &str) -> Result<String, ChinSqlError> {
+        self.full_text_rank(index, ph)
+    }
+
+    fn full_text_rank(&self, index: &FullTextSql, ph: &str) -> Result<String, ChinSqlError> {
+        Ok(format!(
+            "match ({}) against ({ph} in natural language mode)",
+            index.columns.join(", ")
+        ))
+    }
+
+    fn create_full_text(&self, table: &str, columns: &[&str]) -> Result<Vec<String>, ChinSqlError> {
+        Ok(vec![format!(
+            "create fulltext index {} on {}({})",
+            self.quote_ident(&FullText::fts_table(table))?,
+            self.quote_ident(table)?,
+            quote_all(self, columns)?.join(", ")
+        )])
+    }
+
+    fn delete_using(&self, table: &str, using: &str, filter: &str) -> String {
+        format!(" using {table}, {using}{filter}")
+    }
+
+    fn create_index(&self, unique: bool, name: &str, table: &str, columns: &str) -> String {
+        let unique = if unique { "unique " } else { "" };
+        format!("create {unique}index {name} on {table}({columns})")
+    }
+
+    /// Backslashes escape unless `NO_BACKSLASH_ESCAPES` is set.
+    fn str_literal(&self, v: &str) -> String {
+        format!("'{}'", v.replace('\\', "\\\\").replace('\'', "''"))
+    }
+
+    fn time_literal(&self, v: &DateTime<FixedOffset>) -> String {
+        format!("'{}'", v.naive_utc().format("%Y-%m-%d %H:%M:%S%.6f"))
+    }
+}
+
+fn quote_all<D: Dialect + ?Sized>(
+    dialect: &D,
+    names: &[&str],
+) -> Result<Vec<String>, ChinSqlError> {
+    names.iter().map(|name| dialect.quote_ident(name)).collect()
+}
+
+/// All columns as one `tsvector`, null columns count as empty.
+fn tsvector(columns: &[String]) -> String {
+    let text: Vec<String> = columns
+        .iter()
+        .map(|column| format!("coalesce({column}, '')"))
+        .collect();
+    format!("to_tsvector({TS_CONFIG}, {})", text.join(" || ' ' || "))
+}
+
+fn is_json_index(element: &str) -> bool {
+    !element.is_empty() && element.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// The `$.a[0]` path of SQLite and MySQL, keys are always quoted.
+fn json_path<D: Dialect + ?Sized>(dialect: &D, path: &[&str]) -> Result<String, ChinSqlError> {
+    let mut sql = String::from("$");
+    for element in path {
+        if is_json_index(element) {
+            sql.push_str(&format!("[{element}]"));
+        } else if element.contains(['"', '\\']) {
+            return Err(dialect.unsupported(&format!("json path key `{element}`")));
+        } else {
+            sql.push_str(&format!(".\"{element}\""));
+        }
+    }
+    Ok(sql)
+}
+
+fn hex(v: &[u8]) -> String {
+    v.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn rfc3339(v: &DateTime<FixedOffset>) -> String {
+    v.to_rfc3339_opts(SecondsFormat::AutoSi, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ChinSqlError, CreateTableField, CreateTableSql, DbType, Dialect, Froms, FullText, GroupBy,
+        IntoSqlSeg, JoinTable, JoinType, Joins, LogicFieldType, OnConflict, OrderBy,
+        PlaceHolderType, Postgres, SqlBuilder, SqlDeleter, SqlInserter, SqlReader, SqlTypedField,
+        SqlUpdater, Wheres, json_type::Json, test_util::squash,
+    };
+
+    #[test]
+    fn mysql() {
+        let db_type = DbType::MySql;
+        let insert = |on_conflict| {
+            SqlInserter::new("note")
+                .field("id", 1)
+                .field("name", "a")
+                .on_conflict(on_conflict)
+                .into_sql_seg(db_type)
+        };
+
+        let seg = insert(OnConflict::nothing(["id"])).unwrap();
+        assert_eq!(
+            "insert ignore into \"note\"(\"id\",\"name\") values (?, ?)",
+            squash(&seg.seg)
+        );
+        let seg = insert(OnConflict::update(["id"], [])).unwrap();
+        assert_eq!(
+            "insert into \"note\"(\"id\",\"name\") values (?, ?) \
+             on duplicate key update \"name\" = values(\"name\")",
+            squash(&seg.seg)
+        );
+        assert!(
+            insert(OnConflict::update_where(
+                ["id"],
+                [],
+                Wheres::equal("name", "b")
+            ))
+            .is_err()
+        );
+        assert!(
+            SqlInserter::new("note")
+                .field("id", 1)
+                .returning(["id"])
+                .into_sql_seg(db_type)
+                .is_err()
+        );
+
+        let seg = SqlUpdater::new("note")
+            .set("author", "a")
+            .from(Froms::Table {
+                table_name: "user",
+                alias: "u",
+            })
+            .r#where(Wheres::Raw("u.id = note.user_id".into()))
+            .into_sql_seg(db_type)
+            .unwrap();
+        assert_eq!(
+            "update \"note\", \"user\" as \"u\" set \"author\" = ? where u.id = note.user_id",
+            squash(&seg.seg)
+        );
+
+        let seg = SqlDeleter::new("note")
+            .using(Froms::Table {
+                table_name: "user",
+                alias: "u",
+            })
+            .r#where(Wheres::Raw("u.id = note.user_id".into()))
+            .into_sql_seg(db_type)
+            .unwrap();
+        assert_eq!(
+            "delete from \"note\" using \"note\", \"user\" as \"u\" where u.id = note.user_id",
+            squash(&seg.seg)
+        );
+
+        let seg = SqlBuilder::new()
+            .bind("name", "a")
+            .seg("select id from note")
+            .r#where(Wheres::or([
+                Wheres::ilike("name", "b", crate::ILikeType::Fuzzy),
+                Wheres::SOV(vec!["title = ".into(), crate::SegOrVal::named("name")]),
+                Wheres::SOV(vec!["body = ".into(), crate::SegOrVal::named("name")]),
+            ]))
+            .limit(10)
+            .into_sql_seg(db_type)
+            .unwrap();
+        assert_eq!(
+            "select id from note where name like ? escape '!' or title = ? or body = ? limit 10",
+            squash(&seg.seg)
+        );
+        assert_eq!(3, seg.values.len());
+        assert_eq!(
+            "select id from note where name like '%b%' escape '!' or title = 'a' or body = 'a' limit 10",
+            seg.render_inline(db_type).unwrap()
+        );
+
+        let seg = Wheres::in_array("id", vec![1, 2])
+            .build(db_type, &mut PlaceHolderType::positional())
+            .unwrap()
+            .unwrap();
+        assert_eq!("id member of (cast(? as json))", seg.seg);
+
+        let sqls = CreateTableSql {
+            table_name: "note",
+            fields: &[
+                CreateTableField {
+                    name: "id",
+                    kind: LogicFieldType::I64,
+                    not_null: true,
+                },
+                CreateTableField {
+                    name: "title",
+                    kind: LogicFieldType::Varchar(20),
+                    not_null: false,
+                },
+            ],
+            pkey: &["id"],
+            unikeys: &[("title", &["title"])],
+            keys: &[],
+            fts: &[],
+        }
+        .to_owned_sql()
+        .sqls(db_type)
+        .unwrap();
+        assert_eq!(
+            "create table if not exists \"note\" ( \"id\" BIGINT not null, \
+             \"title\" VARCHAR(20) , primary key ( \"id\" ) )",
+            squash(&sqls[0])
+        );
+        assert_eq!(
+            "create unique index \"note_title\" on \"note\"(\"title\")",
+            sqls[1]
+        );
+    }
+
+    #[test]
+    fn full_text() {
+        let index = FullText::new("note", ["title", "body"]).alias("n");
+        let render = |db_type: DbType| {
+            let mut pht = db_type.dialect().placeholder();
+            let seg = index
+                .matches("a b")
+                .build(db_type, &mut pht)
+                .unwrap()
+                .unwrap();
+            (seg.seg, seg.values[0].to_literal(db_type))
+        };
+
+        assert_eq!(
+            (
+                "to_tsvector('simple'::regconfig, coalesce(\"n\".\"title\", '') || ' ' || \
+                 coalesce(\"n\".\"body\", '')) @@ plainto_tsquery('simple'::regconfig, $1)"
+                    .to_owned(),
+                "'a b'".to_owned()
+            ),
+            render(DbType::Postgres)
+        );
+        assert_eq!(
+            (
+                "\"n\".rowid in (select rowid from \"note_fts\" where \"note_fts\" match ?)"
+                    .to_owned(),
+                "'\"a\" \"b\"'".to_owned()
+            ),
+            render(DbType::Sqlite)
+        );
+        assert_eq!(
+            (
+                "match (\"n\".\"title\", \"n\".\"body\") against (? in natural language mode)"
+                    .to_owned(),
+                "'a b'".to_owned()
+            ),
+            render(DbType::MySql)
+        );
+        assert!(matches!(index.matches("  "), Wheres::None));
+    }
+
+    #[test]
+    fn json() {
+        let render = |wheres: Wheres, db_type: DbType| {
+            let mut pht = db_type.dialect().placeholder();
+            wheres.build(db_type, &mut pht).map(|seg| seg.unwrap().seg)
+        };
+        let path = || Wheres::json_path_eq("meta", ["tags", "0", "it's"], "a");
+
+        assert_eq!(
+            "meta->'tags'->0->>'it''s' = $1",
+            render(path(), DbType::Postgres).unwrap()
+        );
+        assert_eq!(
+            "json_extract(meta, '$.\"tags\"[0].\"it''s\"') = ?",
+            render(path(), DbType::Sqlite).unwrap()
+        );
+        assert_eq!(
+            "meta->>'$.\"tags\"[0].\"it''s\"' = ?",
+            render(path(), DbType::MySql).unwrap()
+        );
+        assert_eq!(
+            "meta #>> '{}' = $1",
+            render(
+                Wheres::json_path_eq("meta", Vec::<&str>::new(), "a"),
+                DbType::Postgres
+            )
+            .unwrap()
+        );
+        assert!(render(Wheres::json_path_eq("meta", ["a\"b"], 1), DbType::Sqlite).is_err());
+        assert_eq!(
+            "(meta->>'n')::int4 = $1",
+            render(Wheres::json_path_eq("meta", ["n"], 5), DbType::Postgres).unwrap()
+        );
+        assert_eq!(
+            "(meta->'a'->>0)::float8 > $1",
+            render(
+                Wheres::json_path("meta", ["a", "0"], ">", 0.5),
+                DbType::Postgres
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            "json_extract(meta, '$.\"n\"') = ?",
+            render(Wheres::json_path_eq("meta", ["n"], 5), DbType::Sqlite).unwrap()
+        );
+        assert_eq!(
+            "meta #> '{\"a\",\"b\\\"c\"}' = $1",
+            render(
+                Wheres::json_path_eq("meta", ["a", "b\"c"], Json(1).to_sql_value().unwrap()),
+                DbType::Postgres
+            )
+            .unwrap()
+        );
+
+        let contains = || Wheres::json_contains("meta", vec!["a"]).unwrap();
+        assert_eq!("meta @> $1", render(contains(), DbType::Postgres).unwrap());
+        assert_eq!(
+            "json_contains(meta, ?)",
+            render(contains(), DbType::MySql).unwrap()
+        );
+        assert!(render(contains(), DbType::Sqlite).is_err());
+
+        assert_eq!(
+            "'[\"a\"]'::jsonb",
+            Json(vec!["a"])
+                .to_sql_value()
+                .unwrap()
+                .to_literal(DbType::Postgres)
+        );
+        assert_eq!("JSONB", Postgres.column_type(LogicFieldType::Json));
+    }
+
+    struct Upper;
+
+    impl Dialect for Upper {
+        fn name(&self) -> &'static str {
+            "upper"
+        }
+
+        fn placeholder(&self) -> PlaceHolderType {
+            PlaceHolderType::dollar_number()
+        }
+
+        fn max_params(&self) -> usize {
+            100
+        }
+
+        fn quote_ident(&self, name: &str) -> Result<String, ChinSqlError> {
+            Ok(format!("[{name}]"))
+        }
+
+        fn column_type(&self, kind: LogicFieldType) -> String {
+            Postgres.column_type(kind)
+        }
+
+        fn ilike(&self, key: &str, ph: &str, escape: char) -> String {
+            Postgres.ilike(key, ph, escape)
+        }
+
+        fn array_predicate(&self, key: &str, ph: &str) -> String {
+            format!("{key} = any({ph})")
+        }
+
+        fn limit(&self, limit: usize, _offset: Option<usize>) -> String {
+            format!("fetch first {limit} rows only")
+        }
+
+        fn time_literal(&self, v: &chrono::DateTime<chrono::FixedOffset>) -> String {
+            Postgres.time_literal(v)
+        }
+    }
+
+    #[test]
+    fn custom_dialect() {
+        static UPPER: Upper = Upper;
+        let db_type = DbType::Custom(&UPPER);
+        let seg = SqlBuilder::new()
+            .seg("select id from note")
+            .r#where(Wheres::ilike("name", "a", crate::ILikeType::Original))
+            .limit(5)
+            .into_sql_seg(db_type)
+            .unwrap();
+        assert_eq!(
+            "select id from note where name ilike $1 escape '!' fetch first 5 rows only",
+            squash(&seg.seg)
+        );
+        assert_eq!(100, db_type.max_params());
+    }
+
+    #[test]
+    fn custom_dialect_quotes_typed_fields() {
+        static UPPER: Upper = Upper;
+        let note_id = SqlTypedField::<i64>::new("n", "id");
+        let joins = Joins::new(Froms::Table {
+            table_name: "note",
+            alias: "n",
+        })
+        .join(JoinTable {
+            join_type: JoinType::InnerJoin,
+            table: Froms::Table {
+                table_name: "tag",
+                alias: "t",
+            },
+            conds: vec![
+                (
+                    SqlTypedField::<i64>::new("n", "id"),
+                    SqlTypedField::<i64>::new("t", "note_id"),
+                )
+                    .into(),
+            ],
+        });
+        let reader = || {
+            SqlReader::builder([note_id.erased()], joins.clone().into())
+                .wheres(note_id.v_gt(1))
+                .group_by(GroupBy::Plain(vec![note_id.twn()]))
+                .order_by([OrderBy::Desc(note_id.twn())])
+                .build()
+        };
+
+        let seg = reader().into_sql_seg(DbType::Custom(&UPPER)).unwrap();
+        assert_eq!(
+            "select [n].[id] from [note] as [n] inner join [tag] as [t] on [n].[id] = [t].[note_id] \
+             where [n].[id] > $1 group by [n].[id] order by [n].[id] desc",
+            squash(&seg.seg)
+        );
+
+        // 64 bytes, one over the Postgres limit
+        let field = SqlTypedField::<i64>::new(
+            "n",
+            "xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx",
+        );
+        assert!(
+            field
+                .v_gt(1)
+                .build(DbType::Postgres, &mut PlaceHolderType::dollar_number())
+                .is_err()
+        );
+        assert!(reader().into_sql_seg(DbType::Postgres).is_ok());
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{ChinSqlError, DbType, Ident, SqlExpr, Wheres};
+
+/// The full-text index of a table, see [`CreateTableSql::fts`] for how it
+/// is created. `columns` are the indexed ones in index order, Postgres only
+/// uses the index when the expression matches it exactly.
+///
+/// [`CreateTableSql::fts`]: crate::CreateTableSql::fts
+#[derive(Clone, Debug)]
+pub struct FullText<'a> {
+    table: &'a str,
+    alias: Option<&'a str>,
+    columns: Vec<&'a str>,
+}
+
+impl<'a> FullText<'a> {
+    pub fn new<T: Into<Vec<&'a str>>>(table: &'a str, columns: T) -> Self {
+        Self {
+            table,
+            alias: None,
+            columns: columns.into(),
+        }
+    }
+
+    /// The alias the table has in the query.
+    pub fn alias(mut self, alias: &'a str) -> Self {
+        self.alias.replace(alias);
+        self
+    }
+
+    pub fn table(&self) -> &'a str {
+        self.table
+    }
+
+    pub fn columns(&self) -> &[&'a str] {
+        &self.columns
+    }
+
+    /// The SQLite FTS5 table kept next to `table`.
+    pub fn fts_table(table: &str) -> String {
+        format!("{table}_fts")
+    }
+
+    pub fn to_sql(&self, db_type: DbType) -> Result<FullTextSql, ChinSqlError> {
+        let reference = self.alias.unwrap_or(self.table);
+        Ok(FullTextSql {
+            fts_table: Ident::new(Self::fts_table(self.table)).quote(db_type)?,
+            reference: Ident::new(reference).quote(db_type)?,
+            columns: self
+                .columns
+                .iter()
+                .map(|column| Ident::quote_path([reference, *column], db_type))
+                .collect::<Result<Vec<String>, ChinSqlError>>()?,
+        })
+    }
+
+    /// Rows matching every word of `text`, which is plain user input and
+    /// not a query syntax. Nothing is filtered when `text` is blank.
+    pub fn matches<S: Into<String>>(&self, text: S) -> Wheres<'a> {
+        let text = text.into();
+        if text.trim().is_empty() {
+            return Wheres::None;
+        }
+        Wheres::FullText {
+            index: self.clone(),
+            text,
+        }
+    }
+
+    /// How well a row matches `text`, higher is better on every database.
+    /// Every row ranks `0` when `text` is blank, like [`FullText::matches`]
+    /// does not filter then.
+    pub fn rank<S: Into<String>>(&self, text: S) -> SqlExpr<'a> {
+        let text = text.into();
+        if text.trim().is_empty() {
+            return SqlExpr::Raw(vec!["0".into()]);
+        }
+        SqlExpr::FullTextRank {
+            index: self.clone(),
+            text,
+        }
+    }
+}
+
+/// The quoted names of a [`FullText`] as the dialect renders them.
+pub struct FullTextSql {
+    pub fts_table: String,
+    /// The table or its alias.
+    pub reference: String,
+    /// Qualified with `reference`.
+    pub columns: Vec<String>,
+}
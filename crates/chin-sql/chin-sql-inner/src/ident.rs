@@ -0,0 +1,186 @@
+use std::borrow::Cow;
+
+use crate::{ChinSqlError, DbType};
+
+/// A table, column or alias name which is always quoted in the generated
+/// SQL, so reserved words and mixed-case names keep working.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Ident<'a>(Cow<'a, str>);
+
+impl<'a> Ident<'a> {
+    pub fn new<S: Into<Cow<'a, str>>>(name: S) -> Self {
+        Self(name.into())
+    }
+
+    /// Accept a raw identifier coming from outside (e.g. a sort column from
+    /// a request), only `[A-Za-z_][A-Za-z0-9_]*` is allowed.
+    pub fn parse<S: Into<Cow<'a, str>>>(name: S) -> Result<Self, ChinSqlError> {
+        let name = name.into();
+        if !Self::is_plain(&name) {
+            return Err(ChinSqlError::InvalidIdentifier {
+                ident: name.into_owned(),
+                reason: "only [A-Za-z_][A-Za-z0-9_]* is allowed".to_owned(),
+            });
+        }
+        Ok(Self(name))
+    }
+
+    pub fn is_plain(name: &str) -> bool {
+        let mut chars = name.chars();
+        match chars.next() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            }
+            _ => false,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Double quoted with embedded quotes doubled, valid on SQLite, Postgres
+    /// and MySQL with `ANSI_QUOTES`.
+    pub(crate) fn quoted(&self) -> String {
+        format!("\"{}\"", self.0.replace('"', "\"\""))
+    }
+
+    pub fn quote(&self, db_type: DbType) -> Result<String, ChinSqlError> {
+        let name = self.as_str();
+        if name.is_empty() {
+            return Err(ChinSqlError::InvalidIdentifier {
+                ident: String::new(),
+                reason: "identifier is empty".to_owned(),
+            });
+        }
+        if name.contains('\0') {
+            return Err(ChinSqlError::InvalidIdentifier {
+                ident: name.escape_default().to_string(),
+                reason: "identifier contains nul".to_owned(),
+            });
+        }
+        db_type.dialect().quote_ident(name)
+    }
+
+    /// Quote every part of a dotted path like `alias.column`.
+    pub fn quote_path<'b, I: IntoIterator<Item = &'b str>>(
+        parts: I,
+        db_type: DbType,
+    ) -> Result<String, ChinSqlError> {
+        Ok(parts
+            .into_iter()
+            .map(|p| Ident::new(p).quote(db_type))
+            .collect::<Result<Vec<String>, ChinSqlError>>()?
+            .join("."))
+    }
+}
+
+/// ` returning ...` for the given columns, `*` is kept as is. Empty when
+/// nothing is returned.
+pub(crate) fn returning_clause(columns: &[&str], db_type: DbType) -> Result<String, ChinSqlError> {
+    if columns.is_empty() {
+        return Ok(String::new());
+    }
+    if !db_type.dialect().returning() {
+        return Err(db_type.dialect().unsupported("returning"));
+    }
+    let columns = columns
+        .iter()
+        .map(|e| match *e {
+            "*" => Ok("*".to_owned()),
+            e => Ident::new(e).quote(db_type),
+        })
+        .collect::<Result<Vec<String>, ChinSqlError>>()?;
+    Ok(format!(" returning {}", columns.join(", ")))
+}
+
+impl<'a> From<&'a str> for Ident<'a> {
+    fn from(value: &'a str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for Ident<'_> {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+/// The column side of a predicate or an order by. A path like
+/// `alias.column` is quoted by the dialect when built, raw SQL is kept as is.
+#[derive(Clone, Debug)]
+pub enum SqlKey<'a> {
+    Raw(Cow<'a, str>),
+    Path(Vec<Ident<'a>>),
+}
+
+impl<'a> SqlKey<'a> {
+    pub fn path<I, S>(parts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Cow<'a, str>>,
+    {
+        Self::Path(parts.into_iter().map(Ident::new).collect())
+    }
+
+    pub fn render(&self, db_type: DbType) -> Result<Cow<'_, str>, ChinSqlError> {
+        match self {
+            SqlKey::Raw(raw) => Ok(Cow::Borrowed(raw)),
+            SqlKey::Path(parts) => {
+                Ident::quote_path(parts.iter().map(|e| e.as_str()), db_type).map(Cow::Owned)
+            }
+        }
+    }
+}
+
+impl<'a> From<&'a str> for SqlKey<'a> {
+    fn from(value: &'a str) -> Self {
+        Self::Raw(Cow::Borrowed(value))
+    }
+}
+
+impl From<String> for SqlKey<'_> {
+    fn from(value: String) -> Self {
+        Self::Raw(Cow::Owned(value))
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for SqlKey<'a> {
+    fn from(value: Cow<'a, str>) -> Self {
+        Self::Raw(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DbType, Ident};
+
+    #[test]
+    fn quote_ident() {
+        assert_eq!(
+            "\"order\"",
+            Ident::new("order").quote(DbType::Sqlite).unwrap()
+        );
+        assert_eq!(
+            "\"userName\"",
+            Ident::new("userName").quote(DbType::Postgres).unwrap()
+        );
+        assert_eq!(
+            "\"a\"\"b\"",
+            Ident::new("a\"b").quote(DbType::Postgres).unwrap()
+        );
+        assert_eq!(
+            "\"n\".\"id\"",
+            Ident::quote_path(["n", "id"], DbType::Sqlite).unwrap()
+        );
+
+        assert!(Ident::new("").quote(DbType::Sqlite).is_err());
+        assert!(Ident::new("a\0b").quote(DbType::Sqlite).is_err());
+        assert!(Ident::new("x".repeat(64)).quote(DbType::Postgres).is_err());
+        assert!(Ident::new("x".repeat(64)).quote(DbType::Sqlite).is_ok());
+
+        assert!(Ident::parse("created_at").is_ok());
+        assert!(Ident::parse("id; drop table note").is_err());
+        assert!(Ident::parse("1st").is_err());
+    }
+}
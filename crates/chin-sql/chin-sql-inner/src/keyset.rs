@@ -0,0 +1,385 @@
+use chrono::DateTime;
+use serde::{Deserialize, Deserializer, Serialize, de};
+
+use crate::{
+    ChinSqlError, LimitOffset, OrderBy, SegOrVal, SqlKey, SqlReaderBuilder, SqlTypedField,
+    SqlValue, SqlValueRow, SqlValueStatic, Wheres,
+};
+
+/// The last seen values of the keyset columns, it is handed out to clients
+/// as an opaque token.
+#[derive(Clone, Debug)]
+pub struct KeysetCursor {
+    values: Vec<SqlValueStatic>,
+}
+
+struct KeysetColumn<'a> {
+    key: SqlKey<'a>,
+    column: &'a str,
+    desc: bool,
+}
+
+/// Pages through a reader by comparing against the last seen row instead of
+/// skipping rows with `offset`.
+///
+/// The columns must be not null and unique as a whole, add the primary key
+/// as the last column to break ties.
+pub struct Keyset<'a> {
+    columns: Vec<KeysetColumn<'a>>,
+    cursor: Option<KeysetCursor>,
+    limit: usize,
+}
+
+impl<'a> Keyset<'a> {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            columns: vec![],
+            cursor: None,
+            limit,
+        }
+    }
+
+    fn column<T>(mut self, field: &SqlTypedField<'a, T>, desc: bool) -> Self {
+        self.columns.push(KeysetColumn {
+            key: field.twn(),
+            column: field.alias.unwrap_or(field.field_name),
+            desc,
+        });
+        self
+    }
+
+    pub fn asc<T>(self, field: &SqlTypedField<'a, T>) -> Self {
+        self.column(field, false)
+    }
+
+    pub fn desc<T>(self, field: &SqlTypedField<'a, T>) -> Self {
+        self.column(field, true)
+    }
+
+    pub fn after(mut self, cursor: Option<KeysetCursor>) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Rows after the cursor, `Wheres::None` on the first page.
+    pub fn wheres(&self) -> Result<Wheres<'a>, ChinSqlError> {
+        let Some(cursor) = &self.cursor else {
+            return Ok(Wheres::None);
+        };
+        if cursor.values.len() != self.columns.len() {
+            return Err(ChinSqlError::ValueCountMismatch {
+                context: "cursor".to_owned(),
+                values: cursor.values.len(),
+                columns: self.columns.len(),
+            });
+        }
+
+        let op = |desc: bool| if desc { " < " } else { " > " };
+        let uniform = self.columns.iter().all(|c| c.desc == self.columns[0].desc);
+        if uniform {
+            // row values are supported by both SQLite and Postgres
+            let mut sovs = vec!["(".into()];
+            for (id, column) in self.columns.iter().enumerate() {
+                if id > 0 {
+                    sovs.push(", ".into());
+                }
+                sovs.push(SegOrVal::Key(column.key.clone()));
+            }
+            sovs.push(")".into());
+            sovs.push(op(self.columns[0].desc).into());
+            sovs.push("(".into());
+            for (id, value) in cursor.values.iter().enumerate() {
+                if id > 0 {
+                    sovs.push(", ".into());
+                }
+                sovs.push(SegOrVal::Val(value.clone()));
+            }
+            sovs.push(")".into());
+            return Ok(Wheres::SOV(sovs));
+        }
+
+        let mut ors = Vec::with_capacity(self.columns.len());
+        for (id, column) in self.columns.iter().enumerate() {
+            let mut ands = Vec::with_capacity(id + 1);
+            for (prev, value) in self.columns[..id].iter().zip(cursor.values.iter()) {
+                ands.push(Wheres::equal(prev.key.clone(), value.clone()));
+            }
+            let compare = Wheres::compare(
+                column.key.clone(),
+                op(column.desc).trim(),
+                cursor.values[id].clone(),
+            );
+            if ands.is_empty() {
+                ors.push(compare);
+            } else {
+                ands.push(compare);
+                ors.push(Wheres::and(ands));
+            }
+        }
+        Ok(Wheres::or(ors))
+    }
+
+    pub fn order_by(&self) -> Vec<OrderBy<'a>> {
+        self.columns
+            .iter()
+            .map(|c| {
+                if c.desc {
+                    OrderBy::Desc(c.key.clone())
+                } else {
+                    OrderBy::Asc(c.key.clone())
+                }
+            })
+            .collect()
+    }
+
+    /// The cursor of the next page, taken from the last row of the current
+    /// one. `None` when the page is not full.
+    pub fn next_cursor(&self, rows: &[SqlValueRow]) -> Result<Option<KeysetCursor>, ChinSqlError> {
+        match rows.last() {
+            Some(last) if rows.len() >= self.limit => {
+                let values = self
+                    .columns
+                    .iter()
+                    .map(|c| last.value(c.column).cloned())
+                    .collect::<Result<Vec<SqlValueStatic>, ChinSqlError>>()?;
+                Ok(Some(KeysetCursor::new(values)?))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+impl<'a> SqlReaderBuilder<'a> {
+    /// Combine the keyset predicate with the current filter and replace the
+    /// order by and limit.
+    pub fn keyset(self, keyset: &Keyset<'a>) -> Result<Self, ChinSqlError> {
+        let wheres = keyset.wheres()?;
+        Ok(self
+            .and_wheres(wheres)
+            .order_by(keyset.order_by())
+            .limit(LimitOffset::new(keyset.limit)))
+    }
+}
+
+impl KeysetCursor {
+    pub fn new(values: Vec<SqlValueStatic>) -> Result<Self, ChinSqlError> {
+        for value in values.iter() {
+            if matches!(
+                value,
+                SqlValue::Null(_) | SqlValue::NullUnknown | SqlValue::Array(_) | SqlValue::Json(_)
+            ) {
+                return Err(ChinSqlError::UnexpectedType {
+                    expected: "keyset cursor value",
+                    found: value.type_name(),
+                });
+            }
+        }
+        Ok(Self { values })
+    }
+
+    pub fn values(&self) -> &[SqlValueStatic] {
+        &self.values
+    }
+
+    /// Every value is written as `<tag><len>:<payload>`, then the whole
+    /// text is hex encoded.
+    pub fn encode(&self) -> String {
+        let mut raw = String::new();
+        for value in self.values.iter() {
+            let (tag, payload) = match value {
+                SqlValue::Bool(v) => ('b', v.to_string()),
+                SqlValue::I8(v) => ('c', v.to_string()),
+                SqlValue::I16(v) => ('s', v.to_string()),
+                SqlValue::I32(v) => ('i', v.to_string()),
+                SqlValue::I64(v) => ('l', v.to_string()),
+                SqlValue::F64(v) => ('f', v.to_string()),
+                SqlValue::Str(v) => ('t', v.to_string()),
+                SqlValue::FixedOffset(v) => ('z', v.to_rfc3339()),
+                SqlValue::Utc(v) => ('u', v.to_rfc3339()),
+                SqlValue::Blob(v) => ('x', hex(v)),
+                SqlValue::Array(_)
+                | SqlValue::Json(_)
+                | SqlValue::Null(_)
+                | SqlValue::NullUnknown => {
+                    unreachable!("rejected by KeysetCursor::new")
+                }
+            };
+            raw.push(tag);
+            raw.push_str(&payload.len().to_string());
+            raw.push(':');
+            raw.push_str(&payload);
+        }
+        hex(raw.as_bytes())
+    }
+
+    pub fn decode(token: &str) -> Result<Self, ChinSqlError> {
+        let invalid = || ChinSqlError::InvalidCursor(token.to_owned());
+        let raw = String::from_utf8(unhex(token).ok_or_else(invalid)?).map_err(|_| invalid())?;
+
+        let mut values = vec![];
+        let mut rest = raw.as_str();
+        while !rest.is_empty() {
+            let tag = rest.chars().next().ok_or_else(invalid)?;
+            let (len, tail) = rest[tag.len_utf8()..].split_once(':').ok_or_else(invalid)?;
+            let len: usize = len.parse().map_err(|_| invalid())?;
+            let payload = tail.get(..len).ok_or_else(invalid)?;
+            rest = &tail[len..];
+
+            let value = match tag {
+                'b' => SqlValue::Bool(payload.parse().map_err(|_| invalid())?),
+                'c' => SqlValue::I8(payload.parse().map_err(|_| invalid())?),
+                's' => SqlValue::I16(payload.parse().map_err(|_| invalid())?),
+                'i' => SqlValue::I32(payload.parse().map_err(|_| invalid())?),
+                'l' => SqlValue::I64(payload.parse().map_err(|_| invalid())?),
+                'f' => SqlValue::F64(payload.parse().map_err(|_| invalid())?),
+                't' => SqlValue::Str(payload.to_owned().into()),
+                'z' => SqlValue::FixedOffset(
+                    DateTime::parse_from_rfc3339(payload).map_err(|_| invalid())?,
+                ),
+                'u' => SqlValue::Utc(
+                    DateTime::parse_from_rfc3339(payload)
+                        .map_err(|_| invalid())?
+                        .to_utc(),
+                ),
+                'x' => SqlValue::Blob(unhex(payload).ok_or_else(invalid)?.into()),
+                _ => return Err(invalid()),
+            };
+            values.push(value);
+        }
+
+        Ok(Self { values })
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+impl Serialize for KeysetCursor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.encode())
+    }
+}
+
+impl<'de> Deserialize<'de> for KeysetCursor {
+    fn deserialize<D>(deserializer: D) -> Result<KeysetCursor, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let token = String::deserialize(deserializer)?;
+        KeysetCursor::decode(&token).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use crate::{
+        ChinSqlError, DbType, Froms, IntoSqlSeg, Keyset, KeysetCursor, SqlReader, SqlTypedField,
+        SqlValue, SqlValueRow, test_util::squash,
+    };
+
+    #[test]
+    fn cursor_token() {
+        let cursor = KeysetCursor::new(vec![
+            SqlValue::I64(-3),
+            SqlValue::Str("a:1b".into()),
+            SqlValue::Utc(Utc::now()),
+            SqlValue::F64(0.1),
+        ])
+        .unwrap();
+        let decoded = KeysetCursor::decode(&cursor.encode()).unwrap();
+        assert_eq!(
+            format!("{:?}", cursor.values()),
+            format!("{:?}", decoded.values())
+        );
+
+        assert!(matches!(
+            KeysetCursor::decode("zz"),
+            Err(ChinSqlError::InvalidCursor(token)) if token == "zz"
+        ));
+        assert!(KeysetCursor::decode("6c353a31").is_err());
+        assert!(KeysetCursor::new(vec![SqlValue::NullUnknown]).is_err());
+    }
+
+    #[test]
+    fn keyset_page() {
+        let id = SqlTypedField::<i64>::new("n", "id");
+        let updated = SqlTypedField::<i64>::new("n", "updated_at");
+        let owner = SqlTypedField::<i64>::new("n", "owner");
+        let reader = |keyset: &Keyset<'static>| {
+            SqlReader::builder(
+                [id.erased(), updated.erased()],
+                Froms::Table {
+                    table_name: "note",
+                    alias: "n",
+                },
+            )
+            .wheres(owner.v_eq(7))
+            .keyset(keyset)
+            .unwrap()
+            .build()
+        };
+
+        let keyset = Keyset::new(2).desc(&updated).desc(&id);
+        let seg = reader(&keyset).into_sql_seg(DbType::Sqlite).unwrap();
+        assert_eq!(
+            "select \"n\".\"id\", \"n\".\"updated_at\" from \"note\" as \"n\" \
+             where \"n\".\"owner\" = ? \
+             order by \"n\".\"updated_at\" desc, \"n\".\"id\" desc limit 2",
+            squash(&seg.seg)
+        );
+
+        let rows: Vec<SqlValueRow> = vec![
+            [("id", SqlValue::I64(9)), ("updated_at", SqlValue::I64(100))]
+                .into_iter()
+                .collect(),
+            [("id", SqlValue::I64(4)), ("updated_at", SqlValue::I64(90))]
+                .into_iter()
+                .collect(),
+        ];
+        let token = keyset.next_cursor(&rows).unwrap().unwrap().encode();
+        assert!(keyset.next_cursor(&rows[..1]).unwrap().is_none());
+
+        let cursor = KeysetCursor::decode(&token).unwrap();
+        let keyset = Keyset::new(2).desc(&updated).desc(&id).after(Some(cursor));
+        let seg = reader(&keyset).into_sql_seg(DbType::Postgres).unwrap();
+        assert_eq!(
+            "select \"n\".\"id\", \"n\".\"updated_at\" from \"note\" as \"n\" \
+             where \"n\".\"owner\" = $1 \
+             and (\"n\".\"updated_at\", \"n\".\"id\") < ($2, $3) \
+             order by \"n\".\"updated_at\" desc, \"n\".\"id\" desc limit 2",
+            squash(&seg.seg)
+        );
+        assert_eq!(3, seg.values.len());
+
+        let cursor = KeysetCursor::new(vec![SqlValue::I64(90), SqlValue::I64(4)]).unwrap();
+        let keyset = Keyset::new(2).desc(&updated).asc(&id).after(Some(cursor));
+        let seg = reader(&keyset).into_sql_seg(DbType::Postgres).unwrap();
+        assert_eq!(
+            "select \"n\".\"id\", \"n\".\"updated_at\" from \"note\" as \"n\" \
+             where \"n\".\"owner\" = $1 \
+             and (\"n\".\"updated_at\" < $2 or (\"n\".\"updated_at\" = $3 and \"n\".\"id\" > $4)) \
+             order by \"n\".\"updated_at\" desc, \"n\".\"id\" asc limit 2",
+            squash(&seg.seg)
+        );
+    }
+}
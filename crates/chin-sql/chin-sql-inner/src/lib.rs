@@ -1,23 +1,44 @@
+mod compound;
 mod create_table;
+mod cte;
 mod db_type;
+mod dialect;
+mod full_text;
+mod ident;
+mod keyset;
 mod place_hoder;
 mod sql_builder;
 mod sql_deleter;
+mod sql_expr;
 mod sql_inserter;
+mod sql_render;
+mod sql_row;
 mod sql_updater;
 mod sql_value;
+#[cfg(test)]
+pub(crate) mod test_util;
 mod wheres;
+mod write_guard;
 mod tablefield;
 
+pub use compound::*;
 pub use create_table::*;
+pub use cte::*;
 pub use db_type::*;
+pub use dialect::*;
+pub use full_text::*;
+pub use ident::*;
+pub use keyset::*;
 pub use place_hoder::*;
 pub use sql_builder::*;
 pub use sql_deleter::*;
+pub use sql_expr::*;
 pub use sql_inserter::*;
+pub use sql_row::*;
 pub use sql_updater::*;
 pub use sql_value::*;
 pub use wheres::*;
+pub use write_guard::*;
 pub use tablefield::*;
 
 use thiserror::Error;
@@ -40,10 +61,7 @@ pub trait IntoSqlSeg<'a>: Send {
     where
         Self: Sized,
     {
-        match db_type {
-            DbType::Sqlite => self.into_sql_seg2(db_type, &mut PlaceHolderType::QustionMark),
-            DbType::Postgres => self.into_sql_seg2(db_type, &mut PlaceHolderType::DollarNumber(0)),
-        }
+        self.into_sql_seg2(db_type, &mut db_type.dialect().placeholder())
     }
 
     fn into_sql_seg2(
@@ -81,4 +99,77 @@ pub enum ChinSqlError {
     TransformError(String),
     #[error("FilterBuildError {0}")]
     FilterBuildError(String),
+    #[error("UnfilteredWrite {builder} on `{table}` has no filter, call all_rows() to touch every row")]
+    UnfilteredWrite {
+        builder: &'static str,
+        table: String,
+    },
+    #[error("EmptyBuilder {builder} on `{table}`: {reason}")]
+    EmptyBuilder {
+        builder: &'static str,
+        table: String,
+        reason: &'static str,
+    },
+    #[error("InvalidIdentifier `{ident}`: {reason}")]
+    InvalidIdentifier { ident: String, reason: String },
+    #[error("TooManyParams {params} parameters exceed the limit of {max_params}")]
+    TooManyParams { params: usize, max_params: usize },
+    #[error("UnknownPlaceholder `{0}`")]
+    UnknownPlaceholder(String),
+    /// `found` is the [`SqlValue::type_name`] of the offending value.
+    #[error("UnexpectedType unable to transform {found} into {expected}")]
+    UnexpectedType {
+        expected: &'static str,
+        found: &'static str,
+    },
+    #[error("OutOfRange {value} does not fit into {target}")]
+    OutOfRange { target: String, value: String },
+    /// `context` names the values, e.g. `cursor` or `row 3`.
+    #[error("ValueCountMismatch {context} has {values} values but {columns} columns")]
+    ValueCountMismatch {
+        context: String,
+        values: usize,
+        columns: usize,
+    },
+    #[error("InvalidCursor `{0}` is not a cursor token")]
+    InvalidCursor(String),
+    #[error("EmptyArray unable to infer the type of an empty array")]
+    EmptyArray,
+    #[error("InvalidUpsert {0}")]
+    InvalidUpsert(&'static str),
+    #[error("MissingColumn `{0}` is missing in row")]
+    MissingColumn(String),
+    #[error("ColumnError unable to decode column `{column}`: {source}")]
+    Column {
+        column: String,
+        #[source]
+        source: Box<ChinSqlError>,
+    },
+    #[cfg(feature = "sqlite")]
+    #[error("SqliteError {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[cfg(feature = "postgres")]
+    #[error("PostgresError {0}")]
+    Postgres(#[source] Box<dyn std::error::Error + Sync + Send>),
+}
+
+impl ChinSqlError {
+    /// The column a decode failure happened on, if known.
+    pub fn column(&self) -> Option<&str> {
+        match self {
+            ChinSqlError::Column { column, .. } | ChinSqlError::MissingColumn(column) => {
+                Some(column)
+            }
+            _ => None,
+        }
+    }
+
+    /// The table a refused builder was writing to, if known.
+    pub fn table(&self) -> Option<&str> {
+        match self {
+            ChinSqlError::UnfilteredWrite { table, .. }
+            | ChinSqlError::EmptyBuilder { table, .. } => Some(table),
+            _ => None,
+        }
+    }
 }
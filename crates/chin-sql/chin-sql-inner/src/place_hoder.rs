@@ -1,36 +1,174 @@
 use std::borrow::Cow;
 
-use crate::SqlValue;
+use crate::{ChinSqlError, DbType, Ident, SqlKey, SqlValue};
 
-pub enum PlaceHolderType {
+/// How [`PlaceHolderType`] renders placeholders, match on
+/// [`PlaceHolderType::style`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaceHolderStyle {
     QustionMark,
+    /// `$n`, holding the last number handed out.
     DollarNumber(i32),
+    /// `?` without `:name`, a named value is sent again on every reference.
+    Positional,
+}
+
+struct NamedParam {
+    scope: usize,
+    name: Cow<'static, str>,
+    value: SqlValue<'static>,
+    placeholder: Option<String>,
+}
+
+/// Hands out placeholders while a statement is rendered, and keeps the
+/// named parameters bound so far. Every builder binds in its own scope.
+pub struct PlaceHolderType {
+    style: PlaceHolderStyle,
+    named: Vec<NamedParam>,
+    scope: usize,
+    scopes: usize,
 }
 
 impl PlaceHolderType {
+    /// Stands in for the former `PlaceHolderType::QustionMark` variant.
+    #[allow(non_upper_case_globals)]
+    #[deprecated(note = "use `PlaceHolderType::question_mark()`")]
+    pub const QustionMark: Self = Self::of(PlaceHolderStyle::QustionMark);
+
+    /// Stands in for the former `PlaceHolderType::DollarNumber` variant,
+    /// the next placeholder is `$n + 1`.
+    #[allow(non_snake_case)]
+    #[deprecated(note = "use `PlaceHolderType::dollar_number()`")]
+    pub fn DollarNumber(n: i32) -> Self {
+        Self::of(PlaceHolderStyle::DollarNumber(n))
+    }
+
+    const fn of(style: PlaceHolderStyle) -> Self {
+        Self {
+            style,
+            named: Vec::new(),
+            scope: 0,
+            scopes: 0,
+        }
+    }
+
     pub fn dollar_number() -> Self {
-        PlaceHolderType::DollarNumber(0)
+        Self::of(PlaceHolderStyle::DollarNumber(0))
     }
 
     pub fn question_mark() -> Self {
-        PlaceHolderType::QustionMark
+        Self::of(PlaceHolderStyle::QustionMark)
+    }
+
+    pub fn positional() -> Self {
+        Self::of(PlaceHolderStyle::Positional)
+    }
+
+    pub fn style(&self) -> &PlaceHolderStyle {
+        &self.style
     }
 
     pub fn next_ph(&mut self) -> String {
-        match self {
-            PlaceHolderType::QustionMark => "?".to_owned(),
-            PlaceHolderType::DollarNumber(n) => {
+        match &mut self.style {
+            PlaceHolderStyle::QustionMark | PlaceHolderStyle::Positional => "?".to_owned(),
+            PlaceHolderStyle::DollarNumber(n) => {
                 *n += 1;
                 format!("${n}")
             }
         }
     }
+
+    /// Starts a new scope for the named parameters of a nested builder, it
+    /// neither sees nor clashes with the names bound outside. Returns the
+    /// scope to restore with [`Self::leave_scope`].
+    pub(crate) fn enter_scope(&mut self) -> usize {
+        self.scopes += 1;
+        std::mem::replace(&mut self.scope, self.scopes)
+    }
+
+    pub(crate) fn leave_scope(&mut self, outer: usize) {
+        self.scope = outer;
+    }
+
+    /// Bind a value to `:name` in the current scope, it is only sent once
+    /// however many times the name is referenced.
+    pub(crate) fn bind<S: Into<Cow<'static, str>>>(
+        &mut self,
+        name: S,
+        value: SqlValue<'_>,
+    ) -> Result<(), ChinSqlError> {
+        let name = Ident::parse(name.into())?.as_str().to_owned();
+        if self
+            .named
+            .iter()
+            .any(|e| e.scope == self.scope && e.name == name)
+        {
+            return Err(ChinSqlError::BuilderSqlError(format!(
+                "parameter `:{name}` is bound twice"
+            )));
+        }
+        self.named.push(NamedParam {
+            scope: self.scope,
+            name: name.into(),
+            value: value.live_static(),
+            placeholder: None,
+        });
+        Ok(())
+    }
+
+    /// The placeholder of `:name` in the current scope, with its value when
+    /// this is the first reference and the value has to be pushed. SQLite
+    /// keeps `:name`, which takes the next parameter index on its first
+    /// appearance and is suffixed when an other scope took it already,
+    /// Postgres gets one `$n` shared by every reference and positional `?`
+    /// pushes the value every time.
+    pub fn named_ph(
+        &mut self,
+        name: &str,
+    ) -> Result<(String, Option<SqlValue<'static>>), ChinSqlError> {
+        let index = self
+            .named
+            .iter()
+            .position(|e| e.scope == self.scope && e.name == name)
+            .ok_or_else(|| ChinSqlError::UnknownPlaceholder(format!(":{name}")))?;
+        if let Some(placeholder) = &self.named[index].placeholder {
+            return Ok((placeholder.clone(), None));
+        }
+
+        let placeholder = match self.style {
+            PlaceHolderStyle::QustionMark => {
+                let taken = |ph: &str| {
+                    self.named
+                        .iter()
+                        .any(|e| e.placeholder.as_deref() == Some(ph))
+                };
+                let mut placeholder = format!(":{name}");
+                let mut suffix = 1;
+                while taken(&placeholder) {
+                    placeholder = format!(":{name}_{suffix}");
+                    suffix += 1;
+                }
+                placeholder
+            }
+            PlaceHolderStyle::DollarNumber(_) => self.next_ph(),
+            PlaceHolderStyle::Positional => {
+                return Ok((self.next_ph(), Some(self.named[index].value.clone())));
+            }
+        };
+        let param = &mut self.named[index];
+        param.placeholder = Some(placeholder.clone());
+        Ok((placeholder, Some(param.value.clone())))
+    }
 }
 
 #[derive(Clone, Debug)]
 pub enum SegOrVal<'a> {
     Str(Cow<'a, str>),
     Val(SqlValue<'a>),
+    /// A reference to a value bound by name, see [`PlaceHolderType::bind`].
+    Named(Cow<'a, str>),
+    /// A column quoted by the dialect.
+    Key(SqlKey<'a>),
 }
 
 impl<'a> From<&'a str> for SegOrVal<'a> {
@@ -55,4 +193,53 @@ impl<'a> SegOrVal<'a> {
     pub fn val<T: Into<SqlValue<'a>>>(val: T) -> Self {
         SegOrVal::Val(val.into())
     }
+
+    pub fn named<S: Into<Cow<'a, str>>>(name: S) -> Self {
+        SegOrVal::Named(name.into())
+    }
+
+    pub fn key<S: Into<SqlKey<'a>>>(key: S) -> Self {
+        SegOrVal::Key(key.into())
+    }
+
+    /// Append the segment or the placeholder of the value to `sb`.
+    pub(crate) fn render(
+        self,
+        sb: &mut String,
+        values: &mut Vec<SqlValue<'a>>,
+        db_type: DbType,
+        pht: &mut PlaceHolderType,
+    ) -> Result<(), ChinSqlError> {
+        match self {
+            SegOrVal::Str(s) => sb.push_str(&s),
+            SegOrVal::Key(key) => sb.push_str(&key.render(db_type)?),
+            SegOrVal::Val(value) => {
+                sb.push_str(&pht.next_ph());
+                values.push(value);
+            }
+            SegOrVal::Named(name) => {
+                let (placeholder, value) = pht.named_ph(&name)?;
+                sb.push_str(&placeholder);
+                values.extend(value);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(deprecated)]
+    fn former_variants() {
+        let mut pht = PlaceHolderType::DollarNumber(2);
+        assert_eq!("$3", pht.next_ph());
+        assert_eq!(&PlaceHolderStyle::DollarNumber(3), pht.style());
+
+        let mut pht = PlaceHolderType::QustionMark;
+        assert_eq!("?", pht.next_ph());
+        assert!(matches!(pht.style(), PlaceHolderStyle::QustionMark));
+    }
 }
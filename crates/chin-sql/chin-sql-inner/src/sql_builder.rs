@@ -3,7 +3,8 @@ use std::{borrow::Cow, ops::Deref};
 use chin_tools_types::SharedStr;
 
 use crate::{
-    ChinSqlError, DbType, IntoSqlSeg, SegOrVal, SqlField, SqlSeg, SqlTable, SqlTypedField,
+    ChinSqlError, CompoundQuery, DbType, Ident, IntoSqlSeg, SegOrVal, SelectField, SqlExpr, SqlKey,
+    SqlSeg, SqlTable, SqlTypedField,
 };
 
 use super::{place_hoder::PlaceHolderType, sql_value::SqlValue, wheres::Wheres};
@@ -14,15 +15,29 @@ pub trait CustomSqlSeg<'a>: Send {
 
 enum SqlBuilderSeg<'a> {
     Where(Wheres<'a>),
+    Cond {
+        keyword: &'static str,
+        wheres: Wheres<'a>,
+    },
     LimitOffset(LimitOffset),
     Comma(Vec<&'a str>),
     SegOrVal(SegOrVal<'a>),
-    RawOwned(String),
+    Idents(Vec<Ident<'a>>),
     Custom(Box<dyn CustomSqlSeg<'a>>),
+    Compound(Box<CompoundQuery<'a>>),
+    Select(Vec<SelectField<'a>>),
     Sub {
         alias: &'a str,
         query: SqlBuilder<'a>,
     },
+    Bind {
+        name: Cow<'static, str>,
+        value: SqlValue<'a>,
+    },
+    JoinConds(Vec<JoinCond<'a>>),
+    /// Comma separated keys, e.g. of a group by.
+    Keys(Vec<SqlKey<'a>>),
+    OrderBy(Vec<OrderBy<'a>>),
 }
 
 pub struct SqlBuilder<'a> {
@@ -40,22 +55,29 @@ impl<'a> SqlBuilder<'a> {
         Self { segs: vec![] }
     }
 
+    /// Plain column names are quoted, `*` and expressions such as
+    /// `count(*)` or `name as n` are kept as is.
     pub fn read(table_name: &str, fields: &[&str]) -> Self {
-        Self {
-            segs: vec![SqlBuilderSeg::RawOwned(format!(
-                "select {} from {} ",
-                fields.join(", "),
-                table_name
-            ))],
-        }
+        let mut this = Self::new().seg("select");
+        this.segs.push(SqlBuilderSeg::Keys(
+            fields
+                .iter()
+                .map(|e| {
+                    if Ident::is_plain(e) {
+                        SqlKey::path([e.to_string()])
+                    } else {
+                        SqlKey::Raw(e.to_string().into())
+                    }
+                })
+                .collect(),
+        ));
+        this.seg(" from").ident(table_name.to_owned())
     }
 
     pub fn read_all(table_name: &str) -> Self {
-        Self {
-            segs: vec![SqlBuilderSeg::RawOwned(format!(
-                "select * from {table_name} "
-            ))],
-        }
+        Self::new()
+            .seg("select * from")
+            .ident(table_name.to_owned())
     }
 
     pub fn val<T: Into<SqlValue<'a>>>(mut self, val: T) -> Self {
@@ -64,12 +86,52 @@ impl<'a> SqlBuilder<'a> {
         self
     }
 
+    /// Bind a value to `:name` once, reference it with [`Self::named`] or
+    /// `SegOrVal::named` in wheres as many times as needed. The name is only
+    /// visible in this builder, not in nested or enclosing ones.
+    pub fn bind<S: Into<Cow<'static, str>>, T: Into<SqlValue<'a>>>(
+        mut self,
+        name: S,
+        value: T,
+    ) -> Self {
+        self.segs.push(SqlBuilderSeg::Bind {
+            name: name.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// A reference to a value bound with [`Self::bind`].
+    pub fn named<S: Into<Cow<'a, str>>>(mut self, name: S) -> Self {
+        self.segs
+            .push(SqlBuilderSeg::SegOrVal(SegOrVal::Named(name.into())));
+        self
+    }
+
     pub fn seg<T: Into<Cow<'a, str>>>(mut self, seg: T) -> Self {
         self.segs
             .push(SqlBuilderSeg::SegOrVal(SegOrVal::Str(seg.into())));
         self
     }
 
+    /// A quoted table, column or alias name.
+    pub fn ident<T: Into<Ident<'a>>>(mut self, ident: T) -> Self {
+        self.segs.push(SqlBuilderSeg::Idents(vec![ident.into()]));
+        self
+    }
+
+    /// Comma separated quoted names.
+    pub fn idents<V, T>(mut self, idents: V) -> Self
+    where
+        V: IntoIterator<Item = T>,
+        T: Into<Ident<'a>>,
+    {
+        self.segs.push(SqlBuilderSeg::Idents(
+            idents.into_iter().map(|e| e.into()).collect(),
+        ));
+        self
+    }
+
     pub fn some_then<T, F>(self, cond: Option<T>, trans: F) -> Self
     where
         F: FnOnce(T, Self) -> Self,
@@ -93,11 +155,38 @@ impl<'a> SqlBuilder<'a> {
         self
     }
 
+    pub fn having<T: Into<Wheres<'a>>>(mut self, wheres: T) -> Self {
+        self.segs.push(SqlBuilderSeg::Cond {
+            keyword: "having",
+            wheres: wheres.into(),
+        });
+        self
+    }
+
+    pub fn on<T: Into<Wheres<'a>>>(mut self, wheres: T) -> Self {
+        self.segs.push(SqlBuilderSeg::Cond {
+            keyword: "on",
+            wheres: wheres.into(),
+        });
+        self
+    }
+
     pub fn comma(mut self, values: Vec<&'a str>) -> Self {
         self.segs.push(SqlBuilderSeg::Comma(values));
         self
     }
 
+    pub fn select<V, F>(mut self, fields: V) -> Self
+    where
+        V: IntoIterator<Item = F>,
+        F: Into<SelectField<'a>>,
+    {
+        self.segs.push(SqlBuilderSeg::Select(
+            fields.into_iter().map(|f| f.into()).collect(),
+        ));
+        self
+    }
+
     pub fn sub(mut self, alias: &'a str, query: SqlBuilder<'a>) -> Self {
         self.segs.push(SqlBuilderSeg::Sub { alias, query });
         self
@@ -119,18 +208,23 @@ impl<'a> SqlBuilder<'a> {
         self
     }
 
-    pub fn order_by<'b, T: Into<Vec<OrderBy<'b>>>>(self, orders: T) -> Self {
-        let orders: Vec<String> = orders
+    pub fn order_by<T: Into<Vec<OrderBy<'a>>>>(mut self, orders: T) -> Self {
+        let orders: Vec<OrderBy<'a>> = orders
             .into()
-            .iter()
+            .into_iter()
             .filter(|e| !matches!(e, OrderBy::None))
-            .map(|e| match e {
-                OrderBy::Asc(shared_str) => format!("{} asc", shared_str),
-                OrderBy::Desc(shared_str) => format!("{} desc", shared_str),
-                OrderBy::None => unreachable!(),
-            })
             .collect();
-        self.seg(orders.join(", "))
+        self.segs.push(SqlBuilderSeg::OrderBy(orders));
+        self
+    }
+
+    pub(crate) fn order_by_clause(self, orders: Option<Vec<OrderBy<'a>>>) -> Self {
+        let orders = orders.unwrap_or_default();
+        if orders.iter().all(|e| matches!(e, OrderBy::None)) {
+            self
+        } else {
+            self.seg("order by").order_by(orders)
+        }
     }
 
     pub fn merge<SB: Into<SqlBuilder<'a>>>(mut self, other: SB) -> Self {
@@ -146,9 +240,10 @@ pub struct LimitOffset {
     pub offset: Option<usize>,
 }
 
+#[derive(Clone, Debug)]
 pub enum OrderBy<'a> {
-    Asc(Cow<'a, str>),
-    Desc(Cow<'a, str>),
+    Asc(SqlKey<'a>),
+    Desc(SqlKey<'a>),
     None,
 }
 
@@ -199,18 +294,46 @@ impl<'a> IntoSqlSeg<'a> for SqlBuilder<'a> {
             Err(ChinSqlError::BuilderSqlError("segs is empty".into()))?
         }
 
+        let outer = pht.enter_scope();
+        let seg = self.render_segs(db_type, pht);
+        pht.leave_scope(outer);
+        seg
+    }
+}
+
+impl<'a> SqlBuilder<'a> {
+    fn render_segs(
+        self,
+        db_type: DbType,
+        pht: &mut PlaceHolderType,
+    ) -> Result<SqlSeg<'a>, ChinSqlError> {
+        for seg in self.segs.iter() {
+            if let SqlBuilderSeg::Bind { name, value } = seg {
+                pht.bind(name.clone(), value.clone())?;
+            }
+        }
+
         let mut sb = String::new();
         let mut values: Vec<SqlValue<'a>> = Vec::new();
 
         for seg in self.segs {
             match seg {
                 SqlBuilderSeg::Where(wr) => {
-                    if let Some(ss) = wr.build(db_type, pht) {
+                    if let Some(ss) = wr.build(db_type, pht)? {
                         sb.push_str(" where ");
                         sb.push_str(&ss.seg);
                         values.extend(ss.values)
                     }
                 }
+                SqlBuilderSeg::Cond { keyword, wheres } => {
+                    if let Some(ss) = wheres.build(db_type, pht)? {
+                        sb.push(' ');
+                        sb.push_str(keyword);
+                        sb.push(' ');
+                        sb.push_str(&ss.seg);
+                        values.extend(ss.values)
+                    }
+                }
                 SqlBuilderSeg::Comma(vs) => {
                     sb.push_str(vs.join(", ").as_str());
                 }
@@ -220,36 +343,100 @@ impl<'a> IntoSqlSeg<'a> for SqlBuilder<'a> {
                         values.extend(cs.values)
                     }
                 }
-                SqlBuilderSeg::Sub { alias, query } => {
-                    if let Ok(s) = query.into_sql_seg2(db_type, pht) {
-                        sb.push_str(" (");
-                        sb.push_str(&s.seg);
-                        sb.push_str(") ");
-                        sb.push_str(alias);
-                        values.extend(s.values);
+                SqlBuilderSeg::Compound(query) => {
+                    let SqlSeg { seg, values: vs } = query.into_sql_seg2(db_type, pht)?;
+                    sb.push_str(&seg);
+                    values.extend(vs);
+                }
+                SqlBuilderSeg::Select(fields) => {
+                    let mut selects = Vec::with_capacity(fields.len());
+                    for field in fields {
+                        let SqlSeg { seg, values: vs } = field.into_sql_seg2(db_type, pht)?;
+                        selects.push(seg);
+                        values.extend(vs);
                     }
+                    sb.push_str(&selects.join(", "));
                 }
-                SqlBuilderSeg::RawOwned(raw) => {
-                    sb.push_str(raw.as_str());
+                SqlBuilderSeg::Sub { alias, query } => {
+                    let s = query.into_sql_seg2(db_type, pht)?;
+                    sb.push_str(" (");
+                    sb.push_str(&s.seg);
+                    sb.push_str(") ");
+                    sb.push_str(&Ident::new(alias).quote(db_type)?);
+                    values.extend(s.values);
                 }
-                SqlBuilderSeg::SegOrVal(sql_seg) => match sql_seg {
-                    SegOrVal::Str(s) => {
-                        sb.push_str(&s);
-                        sb.push(' ');
+                SqlBuilderSeg::Idents(idents) => {
+                    let idents = idents
+                        .iter()
+                        .map(|e| e.quote(db_type))
+                        .collect::<Result<Vec<String>, ChinSqlError>>()?;
+                    sb.push_str(&idents.join(", "));
+                }
+                SqlBuilderSeg::SegOrVal(sov) => {
+                    sov.render(&mut sb, &mut values, db_type, pht)?;
+                    sb.push(' ');
+                }
+                SqlBuilderSeg::Bind { .. } => {}
+                SqlBuilderSeg::Keys(keys) => {
+                    let keys = keys
+                        .iter()
+                        .map(|e| e.render(db_type))
+                        .collect::<Result<Vec<Cow<str>>, ChinSqlError>>()?;
+                    sb.push_str(&keys.join(", "));
+                }
+                SqlBuilderSeg::OrderBy(orders) => {
+                    let mut rendered = Vec::with_capacity(orders.len());
+                    for order in orders.iter() {
+                        match order {
+                            OrderBy::Asc(key) => {
+                                rendered.push(format!("{} asc", key.render(db_type)?))
+                            }
+                            OrderBy::Desc(key) => {
+                                rendered.push(format!("{} desc", key.render(db_type)?))
+                            }
+                            OrderBy::None => {}
+                        }
                     }
-                    SegOrVal::Val(val) => {
-                        sb.push_str(&pht.next_ph());
-                        sb.push(' ');
-                        values.push(val);
+                    sb.push_str(&rendered.join(", "));
+                }
+                SqlBuilderSeg::JoinConds(conds) => {
+                    let mut using = vec![];
+                    let mut on = vec![];
+                    for cond in conds {
+                        match cond {
+                            JoinCond::Fields {
+                                l_table,
+                                l_field,
+                                r_table,
+                                r_field,
+                            } => on.push(Wheres::columns(
+                                SqlKey::path([l_table, l_field]),
+                                "=",
+                                SqlKey::path([r_table, r_field]),
+                            )),
+                            JoinCond::Wheres(wheres) => on.push(wheres),
+                            JoinCond::Using(columns) => using.extend(columns),
+                        }
+                    }
+                    if !using.is_empty() {
+                        if !on.is_empty() {
+                            Err(ChinSqlError::BuilderSqlError(
+                                "a join cannot have both using and on conditions".into(),
+                            ))?
+                        }
+                        let columns = using
+                            .into_iter()
+                            .map(|e| Ident::new(e).quote(db_type))
+                            .collect::<Result<Vec<String>, ChinSqlError>>()?;
+                        sb.push_str(&format!(" using ({})", columns.join(", ")));
+                    } else if let Some(ss) = Wheres::and(on).build(db_type, pht)? {
+                        sb.push_str(" on ");
+                        sb.push_str(&ss.seg);
+                        values.extend(ss.values)
                     }
-                },
-                SqlBuilderSeg::LimitOffset(limit_offset) => {
-                    let SqlSeg { seg, values: vs } =
-                        limit_offset.build(pht).ok_or(ChinSqlError::TransformError(
-                            "Unable convert limit offset to sql seg.".to_owned(),
-                        ))?;
-                    sb.push_str(&seg);
-                    values.extend(vs);
+                }
+                SqlBuilderSeg::LimitOffset(LimitOffset { limit, offset }) => {
+                    sb.push_str(&db_type.dialect().limit(limit, offset));
                 }
             };
             if !sb.ends_with(" ") {
@@ -261,6 +448,14 @@ impl<'a> IntoSqlSeg<'a> for SqlBuilder<'a> {
     }
 }
 
+impl<'a> From<CompoundQuery<'a>> for SqlBuilder<'a> {
+    fn from(value: CompoundQuery<'a>) -> Self {
+        Self {
+            segs: vec![SqlBuilderSeg::Compound(Box::new(value))],
+        }
+    }
+}
+
 impl<'a> From<&'a str> for SqlBuilder<'a> {
     fn from(value: &'a str) -> Self {
         Self {
@@ -269,22 +464,32 @@ impl<'a> From<&'a str> for SqlBuilder<'a> {
     }
 }
 
+#[derive(Clone, Debug)]
 pub enum JoinType {
     LeftJoin,
     InnerJoin,
     RightJoin,
+    FullJoin,
+    CrossJoin,
 }
 
-pub struct JoinCond<'a> {
-    l_table: &'a str,
-    l_field: &'a str,
-    r_table: &'a str,
-    r_field: &'a str,
+#[derive(Clone, Debug)]
+pub enum JoinCond<'a> {
+    Fields {
+        l_table: &'a str,
+        l_field: &'a str,
+        r_table: &'a str,
+        r_field: &'a str,
+    },
+    Wheres(Wheres<'a>),
+    /// `using (...)` columns, they replace the `on` clause so mixing them
+    /// with other conditions fails to build.
+    Using(Vec<&'a str>),
 }
 
 impl<'a, T> From<(SqlTypedField<'a, T>, SqlTypedField<'a, T>)> for JoinCond<'a> {
     fn from(value: (SqlTypedField<'a, T>, SqlTypedField<'a, T>)) -> Self {
-        JoinCond {
+        JoinCond::Fields {
             l_table: value.0.table_alias,
             l_field: value.0.field_name,
             r_table: value.1.table_alias,
@@ -292,12 +497,47 @@ impl<'a, T> From<(SqlTypedField<'a, T>, SqlTypedField<'a, T>)> for JoinCond<'a>
         }
     }
 }
+
+impl<'a> From<Wheres<'a>> for JoinCond<'a> {
+    fn from(value: Wheres<'a>) -> Self {
+        JoinCond::Wheres(value)
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct JoinTable<'a> {
     pub join_type: JoinType,
     pub table: Froms<'a>,
     pub conds: Vec<JoinCond<'a>>,
 }
 
+impl<'a> JoinTable<'a> {
+    pub fn on<T: Into<Wheres<'a>>>(join_type: JoinType, table: Froms<'a>, on: T) -> Self {
+        Self {
+            join_type,
+            table,
+            conds: vec![JoinCond::Wheres(on.into())],
+        }
+    }
+
+    pub fn using<T: Into<Vec<&'a str>>>(join_type: JoinType, table: Froms<'a>, columns: T) -> Self {
+        Self {
+            join_type,
+            table,
+            conds: vec![JoinCond::Using(columns.into())],
+        }
+    }
+
+    pub fn cross(table: Froms<'a>) -> Self {
+        Self {
+            join_type: JoinType::CrossJoin,
+            table,
+            conds: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Joins<'a> {
     pub base: Froms<'a>,
     pub joins: Vec<JoinTable<'a>>,
@@ -348,32 +588,15 @@ impl<'a> From<Joins<'a>> for SqlBuilder<'a> {
                 conds,
             } = table;
 
-            match join_type {
-                JoinType::LeftJoin => {
-                    sql_builder = sql_builder.seg("left join");
-                }
-                JoinType::InnerJoin => {
-                    sql_builder = sql_builder.seg("inner join");
-                }
-                JoinType::RightJoin => {
-                    sql_builder = sql_builder.seg("right join");
-                }
-            };
-            sql_builder = sql_builder.merge(table).seg("on");
-            let cond_len = conds.len();
-            for (i, join_cond) in conds.into_iter().enumerate() {
-                sql_builder = sql_builder
-                    .seg(join_cond.l_table)
-                    .seg(".")
-                    .seg(join_cond.l_field)
-                    .seg("=")
-                    .seg(join_cond.r_table)
-                    .seg(".")
-                    .seg(join_cond.r_field);
-                if i < cond_len - 1 {
-                    sql_builder = sql_builder.seg("and");
-                }
-            }
+            sql_builder = sql_builder.seg(match join_type {
+                JoinType::LeftJoin => "left join",
+                JoinType::InnerJoin => "inner join",
+                JoinType::RightJoin => "right join",
+                JoinType::FullJoin => "full join",
+                JoinType::CrossJoin => "cross join",
+            });
+            sql_builder = sql_builder.merge(table);
+            sql_builder.segs.push(SqlBuilderSeg::JoinConds(conds));
         }
 
         sql_builder
@@ -390,6 +613,7 @@ impl<'a> Fields<'a> for String {
     }
 }
 
+#[derive(Clone, Debug)]
 pub enum Froms<'a> {
     Table {
         table_name: &'a str,
@@ -404,6 +628,10 @@ pub enum Froms<'a> {
         table: Vec<SqlReader<'a>>,
         alias: &'a str,
     },
+    Compound {
+        query: Box<CompoundQuery<'a>>,
+        alias: &'a str,
+    },
 }
 
 impl<'a> From<Joins<'a>> for Froms<'a> {
@@ -416,13 +644,13 @@ impl<'a> From<Froms<'a>> for SqlBuilder<'a> {
     fn from(value: Froms<'a>) -> Self {
         match value {
             Froms::Table { table_name, alias } => {
-                SqlBuilder::new().seg(table_name).seg("as").seg(alias)
+                SqlBuilder::new().ident(table_name).seg("as").ident(alias)
             }
             Froms::SubQuery { table, alias } => SqlBuilder::new()
                 .seg("(")
                 .merge(*table)
                 .seg(") as")
-                .seg(alias),
+                .ident(alias),
             Froms::Joins(joins) => (*joins).into(),
             Froms::Union { table, alias } => {
                 let mut sb = SqlBuilder::new().seg("(");
@@ -434,44 +662,58 @@ impl<'a> From<Froms<'a>> for SqlBuilder<'a> {
                         sb = sb.seg("union");
                     }
                 }
-                sb.seg(") as ").seg(alias)
+                sb.seg(") as").ident(alias)
             }
+            Froms::Compound { query, alias } => SqlBuilder::new()
+                .seg("(")
+                .merge(*query)
+                .seg(") as")
+                .ident(alias),
         }
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub enum GroupBy<'a> {
-    Plain(Vec<Cow<'a, str>>),
+    Plain(Vec<SqlKey<'a>>),
     #[default]
     None,
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub enum Having<'a> {
     Custom(Cow<'a, str>),
+    Wheres(Wheres<'a>),
     #[default]
     None,
 }
 
+impl<'a> From<Wheres<'a>> for Having<'a> {
+    fn from(value: Wheres<'a>) -> Self {
+        Self::Wheres(value)
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct SqlReader<'a> {
-    fields: Vec<SqlField<'a>>,
+    fields: Vec<SelectField<'a>>,
     froms: Froms<'a>,
     wheres: Wheres<'a>,
     group_by: GroupBy<'a>,
     having: Having<'a>,
-    order_by: Option<Vec<OrderBy<'a>>>,
-    limit: Option<LimitOffset>,
+    pub(crate) order_by: Option<Vec<OrderBy<'a>>>,
+    pub(crate) limit: Option<LimitOffset>,
 }
 
 impl<'a> SqlReader<'a> {
-    pub fn builder<V: Into<Vec<SqlField<'a>>>>(
-        fields: V,
-        froms: Froms<'a>,
-    ) -> SqlReaderBuilder<'a> {
+    pub fn builder<V, F>(fields: V, froms: Froms<'a>) -> SqlReaderBuilder<'a>
+    where
+        V: IntoIterator<Item = F>,
+        F: Into<SelectField<'a>>,
+    {
         SqlReaderBuilder {
             reader: SqlReader {
-                fields: fields.into(),
+                fields: fields.into_iter().map(|f| f.into()).collect(),
                 froms,
                 wheres: Wheres::None,
                 order_by: None,
@@ -486,6 +728,30 @@ impl<'a> SqlReader<'a> {
         self.limit.replace(limit);
         self
     }
+
+    /// A reader counting the rows of this one as `total`, order by and limit
+    /// are dropped and grouped readers are counted in a subquery.
+    pub fn count_reader(&self) -> SqlReader<'a> {
+        let mut reader = self.clone();
+        reader.order_by = None;
+        reader.limit = None;
+
+        let grouped = matches!(&reader.group_by, GroupBy::Plain(cows) if !cows.is_empty())
+            || !matches!(reader.having, Having::None);
+        if grouped {
+            SqlReader::builder(
+                [SqlExpr::count_all().with_alias("total")],
+                Froms::SubQuery {
+                    table: Box::new(reader),
+                    alias: "counted",
+                },
+            )
+            .build()
+        } else {
+            reader.fields = vec![SqlExpr::count_all().with_alias("total")];
+            reader
+        }
+    }
 }
 
 pub struct SqlReaderBuilder<'a> {
@@ -498,6 +764,13 @@ impl<'a> SqlReaderBuilder<'a> {
         self
     }
 
+    /// And `wheres` with the current filter.
+    pub fn and_wheres(mut self, wheres: Wheres<'a>) -> Self {
+        let current = std::mem::replace(&mut self.reader.wheres, Wheres::None);
+        self.reader.wheres = Wheres::and([current, wheres]);
+        self
+    }
+
     pub fn order_by<T: Into<Vec<OrderBy<'a>>>>(mut self, orders: T) -> Self {
         self.reader.order_by.replace(orders.into());
         self
@@ -525,51 +798,26 @@ impl<'a> SqlReaderBuilder<'a> {
 
 impl<'a> From<SqlReader<'a>> for SqlBuilder<'a> {
     fn from(value: SqlReader<'a>) -> Self {
-        let select = value
-            .fields
-            .iter()
-            .map(|m| match m.alias {
-                Some(alias) => {
-                    format!("{}.{} as {}", m.table_alias, m.field_name, alias)
-                }
-                None => {
-                    format!("{}.{}", m.table_alias, m.field_name)
-                }
-            })
-            .collect::<Vec<String>>()
-            .join(", ");
         SqlBuilder::new()
             .seg("select")
-            .seg(select)
+            .select(value.fields)
             .seg("from")
             .merge(value.froms)
             .r#where(value.wheres)
             .transform(|this| match value.group_by {
-                GroupBy::Plain(cows) => this.seg("order by").seg(cows.join(", ")),
-                GroupBy::None => this,
+                GroupBy::Plain(keys) if !keys.is_empty() => {
+                    let mut this = this.seg("group by");
+                    this.segs.push(SqlBuilderSeg::Keys(keys));
+                    this
+                }
+                GroupBy::Plain(_) | GroupBy::None => this,
             })
             .transform(|this| match value.having {
                 Having::Custom(cow) => this.seg("having").seg(cow),
+                Having::Wheres(wheres) => this.having(wheres),
                 Having::None => this,
             })
-            .transform(|this| match value.order_by {
-                Some(order_by) => {
-                    let c: Vec<String> = order_by
-                        .iter()
-                        .filter_map(|ob| match ob {
-                            OrderBy::Asc(cow) => Some(format!("{} asc", cow)),
-                            OrderBy::Desc(cow) => Some(format!("{} desc", cow)),
-                            OrderBy::None => None,
-                        })
-                        .collect();
-                    if c.len() > 0 {
-                        this.seg("order by").seg(c.join(", "))
-                    } else {
-                        this
-                    }
-                }
-                None => this,
-            })
+            .order_by_clause(value.order_by)
             .transform(|this| match value.limit {
                 Some(lo) => this.limit_offset(lo),
                 None => this,
@@ -591,3 +839,305 @@ impl<'a> IntoSqlSeg<'a> for SqlReader<'a> {
 pub struct SubQueryTable<'a> {
     pub reader: SqlReader<'a>,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ChinSqlError, DbType, Froms, GroupBy, Having, IntoSqlSeg, JoinCond, JoinTable, JoinType,
+        Joins, LimitOffset, OrderBy, SegOrVal, SqlBuilder, SqlExpr, SqlReader, SqlTypedField,
+        Wheres, test_util::squash,
+    };
+
+    fn note_table() -> Froms<'static> {
+        Froms::Table {
+            table_name: "note",
+            alias: "n",
+        }
+    }
+
+    fn grouped_reader<'a>(
+        owner: &'a SqlTypedField<'a, i64>,
+        score: &'a SqlTypedField<'a, i64>,
+    ) -> SqlReader<'a> {
+        SqlReader::builder(
+            [
+                owner.expr().with_alias("owner"),
+                SqlExpr::count_all().with_alias("total"),
+            ],
+            note_table(),
+        )
+        .wheres(score.v_gt(10))
+        .group_by(GroupBy::Plain(vec![owner.twn()]))
+        .having(Wheres::and([
+            Wheres::compare("count(*)", ">", 2),
+            Wheres::compare("sum(n.score)", "<", 100),
+        ]))
+        .order_by([OrderBy::Desc("total".into())])
+        .limit(LimitOffset::new(10).offset(20))
+        .build()
+    }
+
+    #[test]
+    fn group_by_having_sqlite() {
+        let owner = SqlTypedField::<i64>::new("n", "owner");
+        let score = SqlTypedField::<i64>::new("n", "score");
+        let seg = grouped_reader(&owner, &score)
+            .into_sql_seg(DbType::Sqlite)
+            .unwrap();
+        assert_eq!(
+            "select \"n\".\"owner\" as \"owner\", count(*) as \"total\" from \"note\" as \"n\" where \"n\".\"score\" > ? \
+             group by \"n\".\"owner\" having count(*) > ? and sum(n.score) < ? \
+             order by total desc limit 10 offset 20",
+            squash(&seg.seg)
+        );
+        assert_eq!(3, seg.values.len());
+    }
+
+    #[test]
+    fn group_by_having_postgres() {
+        let owner = SqlTypedField::<i64>::new("n", "owner");
+        let score = SqlTypedField::<i64>::new("n", "score");
+        let seg = grouped_reader(&owner, &score)
+            .into_sql_seg(DbType::Postgres)
+            .unwrap();
+        assert_eq!(
+            "select \"n\".\"owner\" as \"owner\", count(*) as \"total\" from \"note\" as \"n\" where \"n\".\"score\" > $1 \
+             group by \"n\".\"owner\" having count(*) > $2 and sum(n.score) < $3 \
+             order by total desc limit 10 offset 20",
+            squash(&seg.seg)
+        );
+        assert_eq!(3, seg.values.len());
+    }
+
+    #[test]
+    fn group_by_without_having() {
+        let owner = SqlTypedField::<i64>::new("n", "owner");
+        let seg = SqlReader::builder([owner.count()], note_table())
+            .group_by(GroupBy::Plain(vec![owner.twn()]))
+            .having(Having::Wheres(Wheres::if_some(None::<i64>, |v| {
+                Wheres::compare("count(*)", ">", v)
+            })))
+            .limit(LimitOffset::new(5))
+            .build()
+            .into_sql_seg(DbType::Postgres)
+            .unwrap();
+        assert_eq!(
+            "select count(\"n\".\"owner\") from \"note\" as \"n\" group by \"n\".\"owner\" limit 5",
+            squash(&seg.seg)
+        );
+
+        let seg = SqlReader::builder([owner.count()], note_table())
+            .group_by(GroupBy::Plain(vec![]))
+            .having(Having::Custom("count(*) > 1".into()))
+            .order_by([OrderBy::None])
+            .build()
+            .into_sql_seg(DbType::Sqlite)
+            .unwrap();
+        assert_eq!(
+            "select count(\"n\".\"owner\") from \"note\" as \"n\" having count(*) > 1",
+            squash(&seg.seg)
+        );
+    }
+
+    #[test]
+    fn join_conditions() {
+        let note_id = SqlTypedField::<i64>::new("n", "id");
+        let tag_note_id = SqlTypedField::<i64>::new("t", "note_id");
+        let tag_weight = SqlTypedField::<i64>::new("t", "weight");
+
+        let joins = Joins::new(note_table())
+            .join(JoinTable {
+                join_type: JoinType::LeftJoin,
+                table: Froms::Table {
+                    table_name: "tag",
+                    alias: "t",
+                },
+                conds: vec![
+                    (
+                        SqlTypedField::<i64>::new("n", "id"),
+                        SqlTypedField::<i64>::new("t", "note_id"),
+                    )
+                        .into(),
+                    JoinCond::Wheres(Wheres::or([tag_weight.v_gt(1), tag_weight.v_lt(-1)])),
+                ],
+            })
+            .join(JoinTable::using(
+                JoinType::FullJoin,
+                Froms::Table {
+                    table_name: "extra",
+                    alias: "e",
+                },
+                ["owner", "kind"],
+            ))
+            .join(JoinTable::cross(Froms::Table {
+                table_name: "config",
+                alias: "c",
+            }));
+
+        let seg = SqlReader::builder([note_id.erased(), tag_note_id.erased()], joins.into())
+            .wheres(note_id.v_eq(3))
+            .build()
+            .into_sql_seg(DbType::Postgres)
+            .unwrap();
+        assert_eq!(
+            "select \"n\".\"id\", \"t\".\"note_id\" from \"note\" as \"n\" \
+             left join \"tag\" as \"t\" on \"n\".\"id\" = \"t\".\"note_id\" and (\"t\".\"weight\" > $1 or \"t\".\"weight\" < $2) \
+             full join \"extra\" as \"e\" using (\"owner\", \"kind\") \
+             cross join \"config\" as \"c\" \
+             where \"n\".\"id\" = $3",
+            squash(&seg.seg)
+        );
+        assert_eq!(3, seg.values.len());
+
+        let mixed = Joins::new(note_table()).join(JoinTable {
+            join_type: JoinType::InnerJoin,
+            table: Froms::Table {
+                table_name: "extra",
+                alias: "e",
+            },
+            conds: vec![
+                JoinCond::Using(vec!["owner"]),
+                JoinCond::Wheres(Wheres::equal("e.kind", 1)),
+            ],
+        });
+        let err = SqlReader::builder([note_id.erased()], mixed.into())
+            .build()
+            .into_sql_seg(DbType::Postgres)
+            .err()
+            .unwrap();
+        assert!(matches!(err, ChinSqlError::BuilderSqlError(_)));
+    }
+
+    #[test]
+    fn count_reader() {
+        let owner = SqlTypedField::<i64>::new("n", "owner");
+        let score = SqlTypedField::<i64>::new("n", "score");
+
+        let page = SqlReader::builder([owner.erased(), score.erased()], note_table())
+            .wheres(score.v_gt(10))
+            .order_by([OrderBy::Desc(score.twn())])
+            .limit(LimitOffset::new(10).offset(20))
+            .build();
+        let seg = page.count_reader().into_sql_seg(DbType::Postgres).unwrap();
+        assert_eq!(
+            "select count(*) as \"total\" from \"note\" as \"n\" where \"n\".\"score\" > $1",
+            squash(&seg.seg)
+        );
+        assert_eq!(1, seg.values.len());
+
+        let seg = grouped_reader(&owner, &score)
+            .count_reader()
+            .into_sql_seg(DbType::Sqlite)
+            .unwrap();
+        assert_eq!(
+            "select count(*) as \"total\" from ( \
+             select \"n\".\"owner\" as \"owner\", count(*) as \"total\" from \"note\" as \"n\" \
+             where \"n\".\"score\" > ? group by \"n\".\"owner\" \
+             having count(*) > ? and sum(n.score) < ? ) as \"counted\"",
+            squash(&seg.seg)
+        );
+        assert_eq!(3, seg.values.len());
+    }
+
+    #[test]
+    fn named_params() {
+        let builder = || {
+            SqlBuilder::new()
+                .bind("since", 100)
+                .seg("select id from note where created_at > ")
+                .named("since")
+                .r#where(Wheres::SOV(vec![
+                    "updated_at > ".into(),
+                    SegOrVal::named("since"),
+                    " and id <> ".into(),
+                    SegOrVal::val(7),
+                ]))
+                .seg("or deleted_at > ")
+                .named("since")
+        };
+
+        let seg = builder().into_sql_seg(DbType::Postgres).unwrap();
+        assert_eq!(
+            "select id from note where created_at > $1 where updated_at > $1 and id <> $2 or deleted_at > $1",
+            squash(&seg.seg)
+        );
+        assert_eq!(2, seg.values.len());
+
+        let seg = builder().into_sql_seg(DbType::Sqlite).unwrap();
+        assert_eq!(
+            "select id from note where created_at > :since where updated_at > :since and id <> ? or deleted_at > :since",
+            squash(&seg.seg)
+        );
+        assert_eq!(2, seg.values.len());
+
+        assert!(
+            SqlBuilder::new()
+                .seg("select")
+                .named("missing")
+                .into_sql_seg(DbType::Sqlite)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn read_fields() {
+        let seg = SqlBuilder::read("note", &["id", "*", "count(*)", "name as n"])
+            .into_sql_seg(DbType::Postgres)
+            .unwrap();
+        assert_eq!(
+            "select \"id\", *, count(*), name as n from \"note\"",
+            squash(&seg.seg)
+        );
+        assert_eq!(
+            "select * from \"note\"",
+            squash(
+                &SqlBuilder::read_all("note")
+                    .into_sql_seg(DbType::Postgres)
+                    .unwrap()
+                    .seg
+            )
+        );
+    }
+
+    #[test]
+    fn nested_named_params() {
+        let inner = |since: i64| {
+            SqlBuilder::new()
+                .bind("since", since)
+                .seg("select id from note where created_at > ")
+                .named("since")
+        };
+        let builder = || {
+            SqlBuilder::new()
+                .bind("since", 100i64)
+                .seg("select * from")
+                .sub("n", inner(200))
+                .seg("where updated_at > ")
+                .named("since")
+        };
+
+        let seg = builder().into_sql_seg(DbType::Postgres).unwrap();
+        assert_eq!(
+            "select * from ( select id from note where created_at > $1 ) \"n\" where updated_at > $2",
+            squash(&seg.seg)
+        );
+        assert_eq!("[I64(200), I64(100)]", format!("{:?}", seg.values));
+
+        let seg = builder().into_sql_seg(DbType::Sqlite).unwrap();
+        assert_eq!(
+            "select * from ( select id from note where created_at > :since ) \"n\" where updated_at > :since_1",
+            squash(&seg.seg)
+        );
+        assert_eq!(2, seg.values.len());
+
+        // the outer binding is not visible to the nested builder
+        assert!(
+            SqlBuilder::new()
+                .bind("since", 100)
+                .seg("select * from")
+                .sub("n", SqlBuilder::new().seg("select").named("since"))
+                .into_sql_seg(DbType::Postgres)
+                .is_err()
+        );
+    }
+}
@@ -1,49 +1,175 @@
-use crate::{ChinSqlError, DbType, IntoSqlSeg};
+use crate::{
+    ChinSqlError, DbType, DryRun, Froms, Ident, IntoSqlSeg, SqlBuilder, guard_filter,
+    returning_clause,
+};
 
 use super::{SqlSeg, place_hoder::PlaceHolderType, sql_value::SqlValue, wheres::Wheres};
 
 pub struct SqlDeleter<'a> {
     table: &'a str,
+    using: Option<Froms<'a>>,
     wheres: Wheres<'a>,
+    returning: Vec<&'a str>,
+    all_rows: bool,
 }
 
 impl<'a> SqlDeleter<'a> {
     pub fn new(table: &'a str) -> Self {
         SqlDeleter {
             table,
+            using: None,
             wheres: Wheres::and([]),
+            returning: vec![],
+            all_rows: false,
         }
     }
 
+    /// Join other tables, the join conditions go to `where`. Postgres gets
+    /// `delete ... using ...`, SQLite has no such clause so the rows are
+    /// picked by `rowid` from a joined subquery instead, which does not work
+    /// on `WITHOUT ROWID` tables.
+    pub fn using(mut self, using: Froms<'a>) -> Self {
+        self.using.replace(using);
+        self
+    }
+
     pub fn r#where(mut self, wheres: Wheres<'a>) -> Self {
         self.wheres = wheres;
         self
     }
-}
 
-impl<'a> IntoSqlSeg<'a> for SqlDeleter<'a> {
-    fn into_sql_seg2(
+    /// Columns of the affected rows to return, `*` for all of them.
+    pub fn returning<T: Into<Vec<&'a str>>>(mut self, columns: T) -> Self {
+        self.returning = columns.into();
+        self
+    }
+
+    /// Run without a filter on purpose, otherwise a filter which collapses
+    /// to nothing is refused with `ChinSqlError::UnfilteredWrite`.
+    pub fn all_rows(mut self) -> Self {
+        self.all_rows = true;
+        self
+    }
+
+    /// Render the statement without refusing a missing filter, to see what
+    /// would be executed.
+    pub fn dry_run(self, db_type: DbType) -> Result<DryRun<'a>, ChinSqlError> {
+        let mut pht = db_type.dialect().placeholder();
+        self.render(db_type, &mut pht, true)
+    }
+
+    fn render(
         self,
         db_type: DbType,
         pht: &mut PlaceHolderType,
-    ) -> Result<SqlSeg<'a>, ChinSqlError> {
+        dry_run: bool,
+    ) -> Result<DryRun<'a>, ChinSqlError> {
         let mut sb = String::new();
         let mut values: Vec<SqlValue<'a>> = Vec::new();
 
+        let table = Ident::new(self.table).quote(db_type)?;
         sb.push_str("delete from ");
-        sb.push_str(self.table);
+        sb.push_str(&table);
 
-        if let Some(filters) = self.wheres.build(db_type, pht) {
-            sb.push_str(" where ");
-            sb.push_str(filters.seg.as_str());
+        let using = match self.using {
+            Some(using) => Some(SqlBuilder::new().merge(using).into_sql_seg2(db_type, pht)?),
+            None => None,
+        };
+        let filters = guard_filter(
+            self.wheres.build(db_type, pht)?,
+            "SqlDeleter",
+            self.table,
+            self.all_rows || dry_run,
+        )?;
+        let all_rows = filters.is_none();
+        let filters = filters.map(|e| (format!(" where {}", e.seg), e.values));
 
-            values.extend(filters.values);
-        } else {
-            Err(ChinSqlError::FilterBuildError(
-                "filter_is_empty".to_string(),
-            ))?
+        let filter = filters
+            .as_ref()
+            .map(|(seg, _)| seg.as_str())
+            .unwrap_or_default();
+        match using {
+            None => sb.push_str(filter),
+            Some(using) => {
+                sb.push_str(&db_type.dialect().delete_using(&table, using.seg.trim(), filter));
+                values.extend(using.values);
+            }
         }
+        if let Some((_, v)) = filters {
+            values.extend(v);
+        }
+
+        sb.push_str(&returning_clause(&self.returning, db_type)?);
+
+        Ok(DryRun {
+            seg: SqlSeg::of(sb, values),
+            all_rows,
+        })
+    }
+}
+
+impl<'a> IntoSqlSeg<'a> for SqlDeleter<'a> {
+    fn into_sql_seg2(
+        self,
+        db_type: DbType,
+        pht: &mut PlaceHolderType,
+    ) -> Result<SqlSeg<'a>, ChinSqlError> {
+        self.render(db_type, pht, false).map(|e| e.seg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DbType, Froms, IntoSqlSeg, SqlDeleter, Wheres, test_util::squash};
+
+    fn deleter() -> SqlDeleter<'static> {
+        SqlDeleter::new("note")
+            .using(Froms::Table {
+                table_name: "user",
+                alias: "u",
+            })
+            .r#where(Wheres::and([
+                Wheres::Raw("u.id = note.user_id".into()),
+                Wheres::equal("u.active", false),
+            ]))
+    }
+
+    #[test]
+    fn delete_using() {
+        let seg = deleter().into_sql_seg(DbType::Postgres).unwrap();
+        assert_eq!(
+            "delete from \"note\" using \"user\" as \"u\" \
+             where u.id = note.user_id and u.active = $1",
+            squash(&seg.seg)
+        );
+
+        let seg = deleter().into_sql_seg(DbType::Sqlite).unwrap();
+        assert_eq!(
+            "delete from \"note\" where rowid in (select \"note\".rowid from \"note\", \
+             \"user\" as \"u\" where u.id = note.user_id and u.active = ?)",
+            squash(&seg.seg)
+        );
+    }
+
+    #[test]
+    fn unfiltered_delete() {
+        let err = SqlDeleter::new("note")
+            .r#where(Wheres::and([Wheres::None]))
+            .into_sql_seg(DbType::Postgres)
+            .unwrap_err();
+        assert_eq!(
+            "UnfilteredWrite SqlDeleter on `note` has no filter, call all_rows() to touch every row",
+            err.to_string()
+        );
+
+        let seg = SqlDeleter::new("note")
+            .all_rows()
+            .into_sql_seg(DbType::Postgres)
+            .unwrap();
+        assert_eq!("delete from \"note\"", squash(&seg.seg));
 
-        Ok(SqlSeg::of(sb, values))
+        let dry_run = deleter().dry_run(DbType::Sqlite).unwrap();
+        assert!(!dry_run.all_rows);
+        assert_eq!(1, dry_run.seg.values.len());
     }
 }
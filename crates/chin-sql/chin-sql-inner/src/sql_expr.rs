@@ -0,0 +1,347 @@
+use std::{
+    borrow::Cow,
+    ops::{Add, Div, Mul, Rem, Sub},
+};
+
+use crate::{
+    ChinSqlError, DbType, FullText, Ident, IntoSqlSeg, PlaceHolderType, SegOrVal, SqlField,
+    SqlReader, SqlSeg, SqlTypedField, SqlValue,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub enum AggregateFunc {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFunc {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AggregateFunc::Count => "count",
+            AggregateFunc::Sum => "sum",
+            AggregateFunc::Avg => "avg",
+            AggregateFunc::Min => "min",
+            AggregateFunc::Max => "max",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+impl ArithOp {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ArithOp::Add => "+",
+            ArithOp::Sub => "-",
+            ArithOp::Mul => "*",
+            ArithOp::Div => "/",
+            ArithOp::Mod => "%",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum SqlExpr<'a> {
+    Column(SqlField<'a>),
+    /// A bare column name, e.g. the column being updated.
+    Ident(Cow<'a, str>),
+    Aggregate {
+        func: AggregateFunc,
+        distinct: bool,
+        arg: Option<Box<SqlExpr<'a>>>, // None means `*`
+    },
+    Func {
+        name: Cow<'a, str>,
+        args: Vec<SqlExpr<'a>>,
+    },
+    Arith {
+        left: Box<SqlExpr<'a>>,
+        op: ArithOp,
+        right: Box<SqlExpr<'a>>,
+    },
+    Value(SqlValue<'a>),
+    Raw(Vec<SegOrVal<'a>>),
+    /// A scalar subquery.
+    Sub(Box<SqlReader<'a>>),
+    /// See [`FullText::rank`].
+    FullTextRank { index: FullText<'a>, text: String },
+}
+
+impl<'a> SqlExpr<'a> {
+    pub fn val<T: Into<SqlValue<'a>>>(v: T) -> Self {
+        Self::Value(v.into())
+    }
+
+    pub fn col<S: Into<Cow<'a, str>>>(name: S) -> Self {
+        Self::Ident(name.into())
+    }
+
+    pub fn sub(query: SqlReader<'a>) -> Self {
+        Self::Sub(Box::new(query))
+    }
+
+    pub fn raw<T: Into<Vec<SegOrVal<'a>>>>(sovs: T) -> Self {
+        Self::Raw(sovs.into())
+    }
+
+    pub fn func<S: Into<Cow<'a, str>>, T: Into<Vec<SqlExpr<'a>>>>(name: S, args: T) -> Self {
+        Self::Func {
+            name: name.into(),
+            args: args.into(),
+        }
+    }
+
+    pub fn coalesce<T: Into<Vec<SqlExpr<'a>>>>(args: T) -> Self {
+        Self::func("coalesce", args)
+    }
+
+    pub fn count_all() -> Self {
+        Self::Aggregate {
+            func: AggregateFunc::Count,
+            distinct: false,
+            arg: None,
+        }
+    }
+
+    pub fn aggregate<T: Into<SqlExpr<'a>>>(func: AggregateFunc, distinct: bool, arg: T) -> Self {
+        Self::Aggregate {
+            func,
+            distinct,
+            arg: Some(Box::new(arg.into())),
+        }
+    }
+
+    pub fn arith<T: Into<SqlExpr<'a>>>(self, op: ArithOp, right: T) -> Self {
+        Self::Arith {
+            left: Box::new(self),
+            op,
+            right: Box::new(right.into()),
+        }
+    }
+
+    pub fn with_alias(self, alias: &'a str) -> SelectField<'a> {
+        SelectField {
+            expr: self,
+            alias: Some(alias),
+        }
+    }
+
+    pub(crate) fn render(
+        self,
+        db_type: DbType,
+        pht: &mut PlaceHolderType,
+        values: &mut Vec<SqlValue<'a>>,
+    ) -> Result<String, ChinSqlError> {
+        let seg = match self {
+            SqlExpr::Column(field) => {
+                Ident::quote_path([field.table_alias, field.field_name], db_type)?
+            }
+            SqlExpr::Ident(name) => Ident::new(name).quote(db_type)?,
+            SqlExpr::Aggregate {
+                func,
+                distinct,
+                arg,
+            } => {
+                let arg = match arg {
+                    Some(arg) => arg.render(db_type, pht, values)?,
+                    None => "*".to_owned(),
+                };
+                if distinct {
+                    format!("{}(distinct {})", func.as_str(), arg)
+                } else {
+                    format!("{}({})", func.as_str(), arg)
+                }
+            }
+            SqlExpr::Func { name, args } => {
+                let args = args
+                    .into_iter()
+                    .map(|e| e.render(db_type, pht, values))
+                    .collect::<Result<Vec<String>, ChinSqlError>>()?;
+                format!("{}({})", name, args.join(", "))
+            }
+            SqlExpr::Arith { left, op, right } => {
+                let left = left.render(db_type, pht, values)?;
+                let right = right.render(db_type, pht, values)?;
+                format!("({} {} {})", left, op.as_str(), right)
+            }
+            SqlExpr::Value(value) => {
+                values.push(value);
+                pht.next_ph()
+            }
+            SqlExpr::Raw(sovs) => {
+                let mut seg = String::new();
+                for sov in sovs {
+                    sov.render(&mut seg, values, db_type, pht)?;
+                }
+                seg
+            }
+            SqlExpr::Sub(query) => {
+                let SqlSeg { seg, values: v } = query.into_sql_seg2(db_type, pht)?;
+                values.extend(v);
+                format!("({})", seg.trim())
+            }
+            SqlExpr::FullTextRank { index, text } => {
+                let dialect = db_type.dialect();
+                let rank = dialect.full_text_rank(&index.to_sql(db_type)?, &pht.next_ph())?;
+                values.push(dialect.full_text_query(&text).into());
+                rank
+            }
+        };
+
+        Ok(seg)
+    }
+}
+
+impl<'a> IntoSqlSeg<'a> for SqlExpr<'a> {
+    fn into_sql_seg2(
+        self,
+        db_type: DbType,
+        pht: &mut PlaceHolderType,
+    ) -> Result<SqlSeg<'a>, ChinSqlError> {
+        let mut values = vec![];
+        let seg = self.render(db_type, pht, &mut values)?;
+        Ok(SqlSeg::of(seg, values))
+    }
+}
+
+macro_rules! arith_ops {
+    ($($tr:ident, $func:ident => $op:expr),*) => {
+        $(
+            impl<'a, T: Into<SqlExpr<'a>>> $tr<T> for SqlExpr<'a> {
+                type Output = SqlExpr<'a>;
+
+                fn $func(self, rhs: T) -> Self::Output {
+                    self.arith($op, rhs)
+                }
+            }
+        )*
+    };
+}
+
+arith_ops!(
+    Add, add => ArithOp::Add,
+    Sub, sub => ArithOp::Sub,
+    Mul, mul => ArithOp::Mul,
+    Div, div => ArithOp::Div,
+    Rem, rem => ArithOp::Mod
+);
+
+impl<'a> From<SqlField<'a>> for SqlExpr<'a> {
+    fn from(value: SqlField<'a>) -> Self {
+        Self::Column(value)
+    }
+}
+
+impl<'a, T> From<SqlTypedField<'a, T>> for SqlExpr<'a> {
+    fn from(value: SqlTypedField<'a, T>) -> Self {
+        Self::Column(value.erased())
+    }
+}
+
+impl<'a> From<SqlValue<'a>> for SqlExpr<'a> {
+    fn from(value: SqlValue<'a>) -> Self {
+        Self::Value(value)
+    }
+}
+
+/// One item of a select list, an expression with an optional alias.
+#[derive(Clone, Debug)]
+pub struct SelectField<'a> {
+    pub expr: SqlExpr<'a>,
+    pub alias: Option<&'a str>,
+}
+
+impl<'a> From<SqlExpr<'a>> for SelectField<'a> {
+    fn from(value: SqlExpr<'a>) -> Self {
+        Self {
+            expr: value,
+            alias: None,
+        }
+    }
+}
+
+impl<'a> From<SqlField<'a>> for SelectField<'a> {
+    fn from(value: SqlField<'a>) -> Self {
+        Self {
+            alias: value.alias,
+            expr: SqlExpr::Column(value),
+        }
+    }
+}
+
+impl<'a> From<&SqlField<'a>> for SelectField<'a> {
+    fn from(value: &SqlField<'a>) -> Self {
+        value.clone().into()
+    }
+}
+
+impl<'a, T> From<SqlTypedField<'a, T>> for SelectField<'a> {
+    fn from(value: SqlTypedField<'a, T>) -> Self {
+        value.erased().into()
+    }
+}
+
+impl<'a> IntoSqlSeg<'a> for SelectField<'a> {
+    fn into_sql_seg2(
+        self,
+        db_type: DbType,
+        pht: &mut PlaceHolderType,
+    ) -> Result<SqlSeg<'a>, ChinSqlError> {
+        let SqlSeg { seg, values } = self.expr.into_sql_seg2(db_type, pht)?;
+        match self.alias {
+            Some(alias) => Ok(SqlSeg::of(
+                format!("{seg} as {}", Ident::new(alias).quote(db_type)?),
+                values,
+            )),
+            None => Ok(SqlSeg::of(seg, values)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        DbType, Froms, IntoSqlSeg, SelectField, SqlExpr, SqlReader, SqlTypedField, SqlValue,
+    };
+
+    #[test]
+    fn aggregate_select() {
+        let id = SqlTypedField::<i64>::new("n", "id");
+        let updated = SqlTypedField::<i64>::new("n", "updated_at");
+        let score = SqlTypedField::<i64>::new("n", "score");
+
+        let reader = SqlReader::builder(
+            [
+                SelectField::from(id.erased()),
+                SqlExpr::count_all().with_alias("total"),
+                updated.max().with_alias("last"),
+                SqlExpr::coalesce([score.sum(), SqlExpr::val(0)]).with_alias("score"),
+                (score.expr() * SqlExpr::val(2) + SqlValue::I64(1)).with_alias("weight"),
+            ],
+            Froms::Table {
+                table_name: "note",
+                alias: "n",
+            },
+        )
+        .build();
+
+        let seg = reader.into_sql_seg(DbType::Postgres).unwrap();
+        assert_eq!(
+            "select \"n\".\"id\", count(*) as \"total\", max(\"n\".\"updated_at\") as \"last\", \
+             coalesce(sum(\"n\".\"score\"), $1) as \"score\", ((\"n\".\"score\" * $2) + $3) as \"weight\" \
+             from \"note\" as \"n\" ",
+            seg.seg
+        );
+        assert_eq!(3, seg.values.len());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{ChinSqlError, DbType, IntoSqlSeg, PlaceHolderType};
+use crate::{ChinSqlError, DbType, Ident, IntoSqlSeg, PlaceHolderType, Wheres, returning_clause};
 
 use super::{SqlSeg, sql_value::SqlValue};
 
@@ -6,17 +6,61 @@ pub struct SqlInserter<'a> {
     table: &'a str,
     fields: Vec<(&'a str, SqlValue<'a>)>,
     extra: Vec<(&'a str, SqlValue<'a>)>,
-    on_conflict: OnConflict,
+    on_conflict: OnConflict<'a>,
+    returning: Vec<&'a str>,
 }
 
 #[derive(Default, Clone, Debug)]
-pub enum OnConflict {
+pub enum OnConflict<'a> {
     Ignore,
+    /// `insert or replace` on SQLite, which deletes the old row first.
+    /// Elsewhere every inserted column but the comma separated target is
+    /// updated.
     Replace(String),
+    /// `on conflict (target) do nothing`.
+    Nothing {
+        target: Vec<&'a str>,
+    },
+    /// `on conflict (target) do update set col = excluded.col` for every
+    /// column of `set`, or every inserted column but the target when `set`
+    /// is empty. The row is left alone when `filter` does not match.
+    Update {
+        target: Vec<&'a str>,
+        set: Vec<&'a str>,
+        filter: Wheres<'a>,
+    },
     #[default]
     Default,
 }
 
+impl<'a> OnConflict<'a> {
+    pub fn nothing<T: Into<Vec<&'a str>>>(target: T) -> Self {
+        Self::Nothing {
+            target: target.into(),
+        }
+    }
+
+    pub fn update<T: Into<Vec<&'a str>>, S: Into<Vec<&'a str>>>(target: T, set: S) -> Self {
+        Self::Update {
+            target: target.into(),
+            set: set.into(),
+            filter: Wheres::None,
+        }
+    }
+
+    pub fn update_where<T: Into<Vec<&'a str>>, S: Into<Vec<&'a str>>>(
+        target: T,
+        set: S,
+        filter: Wheres<'a>,
+    ) -> Self {
+        Self::Update {
+            target: target.into(),
+            set: set.into(),
+            filter,
+        }
+    }
+}
+
 impl<'a> SqlInserter<'a> {
     pub fn new(table: &'static str) -> Self {
         SqlInserter {
@@ -24,6 +68,7 @@ impl<'a> SqlInserter<'a> {
             fields: vec![],
             extra: vec![],
             on_conflict: OnConflict::default(),
+            returning: vec![],
         }
     }
 
@@ -46,10 +91,16 @@ impl<'a> SqlInserter<'a> {
         self
     }
 
-    pub fn on_conflict(mut self, on_conflict: OnConflict) -> Self {
+    pub fn on_conflict(mut self, on_conflict: OnConflict<'a>) -> Self {
         self.on_conflict = on_conflict;
         self
     }
+
+    /// Columns of the affected rows to return, `*` for all of them.
+    pub fn returning<T: Into<Vec<&'a str>>>(mut self, columns: T) -> Self {
+        self.returning = columns.into();
+        self
+    }
 }
 
 impl<'a> IntoSqlSeg<'a> for SqlInserter<'a> {
@@ -59,63 +110,453 @@ impl<'a> IntoSqlSeg<'a> for SqlInserter<'a> {
         pht: &mut PlaceHolderType,
     ) -> Result<SqlSeg<'a>, ChinSqlError> {
         if self.fields.is_empty() {
-            return Err(ChinSqlError::BuilderSqlError("insert files is empty".to_owned()));
+            return Err(ChinSqlError::EmptyBuilder {
+                builder: "SqlInserter",
+                table: self.table.to_owned(),
+                reason: "no field to insert",
+            });
         }
 
-        let mut sql = String::new();
-        sql.push_str("insert ");
-        if matches!(db_type, DbType::Sqlite) {
-            match self.on_conflict {
-                OnConflict::Ignore => sql.push_str(" or ignore "),
-                OnConflict::Replace(_) => sql.push_str(" or replace "),
-                OnConflict::Default => {}
-            }
+        let keys: Vec<&str> = self.fields.iter().map(|(key, _)| *key).collect();
+        let sql = render_insert(self.table, &keys, 1, &self.on_conflict, db_type, pht)?;
+        let mut values: Vec<SqlValue<'a>> = self.fields.into_iter().map(|e| e.1).collect();
+        let SqlSeg { seg, values: vs } = render_conflict(&keys, self.on_conflict, db_type, pht)?;
+        values.extend(vs);
+
+        let returning = returning_clause(&self.returning, db_type)?;
+
+        Ok(SqlSeg::of(sql + &seg + &returning, values))
+    }
+}
+
+/// Many rows with the same columns, split into as few statements as the
+/// bound parameter limit of the database allows.
+pub struct SqlBatchInserter<'a> {
+    table: &'a str,
+    columns: Vec<&'a str>,
+    rows: Vec<Vec<SqlValue<'a>>>,
+    on_conflict: OnConflict<'a>,
+    max_params: Option<usize>,
+    returning: Vec<&'a str>,
+}
+
+impl<'a> SqlBatchInserter<'a> {
+    pub fn new<T: Into<Vec<&'a str>>>(table: &'a str, columns: T) -> Self {
+        Self {
+            table,
+            columns: columns.into(),
+            rows: vec![],
+            on_conflict: OnConflict::default(),
+            max_params: None,
+            returning: vec![],
         }
-        sql.push_str(" into ");
-        sql.push_str(self.table);
-        sql.push('(');
-        sql.push_str(
-            self.fields
-                .iter()
-                .map(|(key, _)| *key)
-                .collect::<Vec<&str>>()
-                .join(",")
-                .as_str(),
-        );
-        sql.push_str(") values (");
-
-        let mut pht_vec = Vec::with_capacity(self.fields.len());
-        for _ in self.fields.iter() {
-            pht_vec.push(pht.next_ph());
-        }
-
-        sql.push_str(pht_vec.join(", ").as_str());
-        sql.push(')');
-        if matches!(db_type, DbType::Postgres) {
-            match self.on_conflict {
-                OnConflict::Ignore => sql.push_str(" ON CONFLICT DO NOTHING"),
-                OnConflict::Replace(cond) => {
-                    sql.push_str(" ON CONFLICT (");
-                    sql.push_str(&cond);
-                    sql.push_str(") DO UPDATE SET ");
-
-                    sql.push_str(self.fields[0].0);
-                    sql.push_str(" = ");
-                    sql.push_str(pht_vec[0].as_str());
-
-                    for (id, pht) in pht_vec.iter().enumerate().skip(1) {
-                        sql.push_str(", ");
-                        sql.push_str(self.fields[id].0);
-                        sql.push('=');
-                        sql.push_str(pht);
-                    }
+    }
+
+    pub fn row<T: Into<SqlValue<'a>>, V: IntoIterator<Item = T>>(mut self, values: V) -> Self {
+        self.rows
+            .push(values.into_iter().map(|e| e.into()).collect());
+        self
+    }
+
+    pub fn rows<T, V, R>(self, rows: R) -> Self
+    where
+        T: Into<SqlValue<'a>>,
+        V: IntoIterator<Item = T>,
+        R: IntoIterator<Item = V>,
+    {
+        rows.into_iter().fold(self, |this, row| this.row(row))
+    }
+
+    pub fn on_conflict(mut self, on_conflict: OnConflict<'a>) -> Self {
+        self.on_conflict = on_conflict;
+        self
+    }
+
+    /// Columns of the affected rows to return, `*` for all of them.
+    pub fn returning<T: Into<Vec<&'a str>>>(mut self, columns: T) -> Self {
+        self.returning = columns.into();
+        self
+    }
+
+    /// Override [`DbType::max_params`], e.g. for SQLite builds older than
+    /// 3.32 which only allow 999 parameters.
+    pub fn max_params(mut self, max_params: usize) -> Self {
+        self.max_params = Some(max_params);
+        self
+    }
+
+    /// One statement per batch, every batch starts its placeholders from
+    /// the beginning.
+    pub fn build(self, db_type: DbType) -> Result<Vec<SqlSeg<'a>>, ChinSqlError> {
+        if self.columns.is_empty() {
+            return Err(ChinSqlError::EmptyBuilder {
+                builder: "SqlBatchInserter",
+                table: self.table.to_owned(),
+                reason: "no column to insert",
+            });
+        }
+        if let Some((id, row)) = self
+            .rows
+            .iter()
+            .enumerate()
+            .find(|(_, row)| row.len() != self.columns.len())
+        {
+            return Err(ChinSqlError::ValueCountMismatch {
+                context: format!("row {id}"),
+                values: row.len(),
+                columns: self.columns.len(),
+            });
+        }
+
+        let max_params = self.max_params.unwrap_or(db_type.max_params());
+        // the upsert filter binds its values again in every batch
+        let conflict_params = render_conflict(
+            &self.columns,
+            self.on_conflict.clone(),
+            db_type,
+            &mut db_type.dialect().placeholder(),
+        )?
+        .values
+        .len();
+        let batch_rows = max_params.saturating_sub(conflict_params) / self.columns.len();
+        if batch_rows == 0 {
+            return Err(ChinSqlError::TooManyParams {
+                params: self.columns.len() + conflict_params,
+                max_params,
+            });
+        }
+
+        let returning = returning_clause(&self.returning, db_type)?;
+        let mut segs = Vec::with_capacity(self.rows.len().div_ceil(batch_rows));
+        let mut rows = self.rows.into_iter().peekable();
+        while rows.peek().is_some() {
+            let batch: Vec<Vec<SqlValue<'a>>> = rows.by_ref().take(batch_rows).collect();
+            let mut pht = db_type.dialect().placeholder();
+            let sql = render_insert(
+                self.table,
+                &self.columns,
+                batch.len(),
+                &self.on_conflict,
+                db_type,
+                &mut pht,
+            )?;
+            let SqlSeg { seg, values } =
+                render_conflict(&self.columns, self.on_conflict.clone(), db_type, &mut pht)?;
+            segs.push(SqlSeg::of(
+                sql + &seg + &returning,
+                batch.into_iter().flatten().chain(values).collect(),
+            ));
+        }
+
+        Ok(segs)
+    }
+}
+
+fn render_insert(
+    table: &str,
+    keys: &[&str],
+    rows: usize,
+    on_conflict: &OnConflict<'_>,
+    db_type: DbType,
+    pht: &mut PlaceHolderType,
+) -> Result<String, ChinSqlError> {
+    let mut sql = String::new();
+    sql.push_str("insert ");
+    if let Some(modifier) = db_type.dialect().insert_modifier(on_conflict) {
+        sql.push(' ');
+        sql.push_str(modifier);
+        sql.push(' ');
+    }
+    let columns = keys
+        .iter()
+        .map(|key| Ident::new(*key).quote(db_type))
+        .collect::<Result<Vec<String>, ChinSqlError>>()?;
+
+    sql.push_str(" into ");
+    sql.push_str(&Ident::new(table).quote(db_type)?);
+    sql.push('(');
+    sql.push_str(columns.join(",").as_str());
+    sql.push_str(") values ");
+
+    let tuples: Vec<String> = (0..rows)
+        .map(|_| {
+            let pht_vec: Vec<String> = keys.iter().map(|_| pht.next_ph()).collect();
+            format!("({})", pht_vec.join(", "))
+        })
+        .collect();
+    sql.push_str(tuples.join(", ").as_str());
+
+    Ok(sql)
+}
+
+fn render_conflict<'a>(
+    keys: &[&str],
+    on_conflict: OnConflict<'a>,
+    db_type: DbType,
+    pht: &mut PlaceHolderType,
+) -> Result<SqlSeg<'a>, ChinSqlError> {
+    let quote_all = |names: &[&str]| {
+        names
+            .iter()
+            .map(|e| Ident::new(*e).quote(db_type))
+            .collect::<Result<Vec<String>, ChinSqlError>>()
+    };
+    let update_set = |target: &[&str], set: Vec<&str>| {
+        let set = if set.is_empty() {
+            keys.iter()
+                .filter(|key| !target.contains(key))
+                .copied()
+                .collect()
+        } else {
+            set
+        };
+        if set.is_empty() {
+            return Err(ChinSqlError::InvalidUpsert("no column to update"));
+        }
+        quote_all(&set)
+    };
+
+    let dialect = db_type.dialect();
+    let mut sql = String::new();
+    let mut values = vec![];
+    match on_conflict {
+        OnConflict::Default => {}
+        _ if dialect.insert_modifier(&on_conflict).is_some() => {}
+        OnConflict::Ignore => sql.push_str(&dialect.upsert(&[], &[])?),
+        OnConflict::Replace(cond) => {
+            let target = cond
+                .split(',')
+                .map(|e| Ident::parse(e.trim()))
+                .collect::<Result<Vec<Ident>, ChinSqlError>>()?;
+            let names = target.iter().map(Ident::as_str).collect::<Vec<&str>>();
+            sql.push_str(&dialect.upsert(&quote_all(&names)?, &update_set(&names, vec![])?)?);
+        }
+        OnConflict::Nothing { target } => {
+            sql.push_str(&dialect.upsert(&quote_all(&target)?, &[])?);
+        }
+        OnConflict::Update {
+            target,
+            set,
+            filter,
+        } => {
+            if target.is_empty() {
+                return Err(ChinSqlError::InvalidUpsert("target is empty"));
+            }
+            sql.push_str(&dialect.upsert(&quote_all(&target)?, &update_set(&target, set)?)?);
+            if let Some(ss) = filter.build(db_type, pht)? {
+                if !dialect.upsert_filter() {
+                    return Err(dialect.unsupported("filtered upserts"));
                 }
-                OnConflict::Default => {}
+                sql.push_str(" where ");
+                sql.push_str(&ss.seg);
+                values.extend(ss.values);
             }
         }
+    }
+
+    Ok(SqlSeg::of(sql, values))
+}
 
-        let values = self.fields.into_iter().map(|e| e.1).collect();
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ChinSqlError, DbType, IntoSqlSeg, OnConflict, SqlBatchInserter, SqlDeleter, SqlInserter,
+        SqlUpdater, Wheres, test_util::squash,
+    };
+
+    #[test]
+    fn single_insert() {
+        let seg = SqlInserter::new("note")
+            .field("id", 1)
+            .field("order", "a")
+            .on_conflict(OnConflict::Replace("id".to_owned()))
+            .into_sql_seg(DbType::Postgres)
+            .unwrap();
+        assert_eq!(
+            "insert  into \"note\"(\"id\",\"order\") values ($1, $2) \
+             on conflict (\"id\") do update set \"order\" = excluded.\"order\"",
+            seg.seg
+        );
+        assert!(!seg.seg.contains("\"id\" = excluded"));
+
+        // only the conflict target was given, nothing is left to update
+        assert!(matches!(
+            SqlInserter::new("note")
+                .field("id", 1)
+                .on_conflict(OnConflict::Replace("id".to_owned()))
+                .into_sql_seg(DbType::Postgres),
+            Err(ChinSqlError::InvalidUpsert(_))
+        ));
+
+        assert!(
+            SqlInserter::new("note")
+                .field("id", 1)
+                .on_conflict(OnConflict::Replace("id) do nothing; --".to_owned()))
+                .into_sql_seg(DbType::Postgres)
+                .is_err()
+        );
+    }
 
-        Ok(SqlSeg::of(sql, values))
+    #[test]
+    fn batch_insert() {
+        let segs = SqlBatchInserter::new("note", ["id", "name"])
+            .rows((0..5).map(|id| [id.to_string(), format!("n{id}")]))
+            .on_conflict(OnConflict::Ignore)
+            .max_params(4)
+            .build(DbType::Sqlite)
+            .unwrap();
+        assert_eq!(3, segs.len());
+        assert_eq!(
+            "insert  or ignore  into \"note\"(\"id\",\"name\") values (?, ?), (?, ?)",
+            segs[0].seg
+        );
+        assert_eq!(4, segs[0].values.len());
+        assert_eq!(
+            "insert  or ignore  into \"note\"(\"id\",\"name\") values (?, ?)",
+            segs[2].seg
+        );
+
+        let segs = SqlBatchInserter::new("note", ["id", "name"])
+            .rows((0..40000).map(|id| [id.to_string(), format!("n{id}")]))
+            .on_conflict(OnConflict::Ignore)
+            .build(DbType::Postgres)
+            .unwrap();
+        assert_eq!(2, segs.len());
+        assert_eq!(65534, segs[0].values.len());
+        assert!(
+            segs[0]
+                .seg
+                .contains("($65533, $65534) on conflict do nothing")
+        );
+        assert!(
+            segs[1]
+                .seg
+                .starts_with("insert  into \"note\"(\"id\",\"name\") values ($1, $2)")
+        );
+
+        let filtered = |max_params| {
+            SqlBatchInserter::new("note", ["id", "name"])
+                .rows((0..3).map(|id| [id.to_string(), format!("n{id}")]))
+                .on_conflict(OnConflict::update_where(
+                    ["id"],
+                    ["name"],
+                    Wheres::equal("note.name", "x"),
+                ))
+                .max_params(max_params)
+                .build(DbType::Postgres)
+        };
+        let segs = filtered(4).unwrap();
+        assert_eq!(3, segs.len());
+        assert!(segs.iter().all(|e| e.values.len() == 3));
+        assert!(segs[0].seg.ends_with("where note.name = $3"));
+        assert!(matches!(
+            filtered(2),
+            Err(ChinSqlError::TooManyParams {
+                params: 3,
+                max_params: 2
+            })
+        ));
+
+        assert!(matches!(
+            SqlBatchInserter::new("note", ["id", "name"])
+                .row([1])
+                .build(DbType::Sqlite),
+            Err(ChinSqlError::ValueCountMismatch {
+                values: 1,
+                columns: 2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn upsert() {
+        let insert = |on_conflict| {
+            SqlInserter::new("note")
+                .field("id", 1)
+                .field("name", "a")
+                .field("version", 2)
+                .on_conflict(on_conflict)
+        };
+
+        for (db_type, expected) in [
+            (
+                DbType::Sqlite,
+                "insert into \"note\"(\"id\",\"name\",\"version\") values (?, ?, ?) \
+                 on conflict (\"id\") do update set \"name\" = excluded.\"name\", \
+                 \"version\" = excluded.\"version\" where \"note\".\"version\" < ?",
+            ),
+            (
+                DbType::Postgres,
+                "insert into \"note\"(\"id\",\"name\",\"version\") values ($1, $2, $3) \
+                 on conflict (\"id\") do update set \"name\" = excluded.\"name\", \
+                 \"version\" = excluded.\"version\" where \"note\".\"version\" < $4",
+            ),
+        ] {
+            let seg = insert(OnConflict::update_where(
+                ["id"],
+                [],
+                Wheres::compare("\"note\".\"version\"", "<", 2),
+            ))
+            .into_sql_seg(db_type)
+            .unwrap();
+            assert_eq!(expected, squash(&seg.seg));
+            assert_eq!(4, seg.values.len());
+        }
+
+        let seg = insert(OnConflict::update(["id"], ["name"]))
+            .into_sql_seg(DbType::Sqlite)
+            .unwrap();
+        assert!(
+            seg.seg
+                .ends_with("on conflict (\"id\") do update set \"name\" = excluded.\"name\"")
+        );
+
+        let seg = insert(OnConflict::nothing(["id", "name"]))
+            .into_sql_seg(DbType::Postgres)
+            .unwrap();
+        assert!(
+            seg.seg
+                .ends_with("on conflict (\"id\", \"name\") do nothing")
+        );
+
+        assert!(matches!(
+            insert(OnConflict::update(Vec::<&str>::new(), ["name"])).into_sql_seg(DbType::Sqlite),
+            Err(ChinSqlError::InvalidUpsert("target is empty"))
+        ));
+    }
+
+    #[test]
+    fn returning() {
+        let seg = SqlInserter::new("note")
+            .field("name", "a")
+            .returning(["id", "created_at"])
+            .into_sql_seg(DbType::Sqlite)
+            .unwrap();
+        assert_eq!(
+            "insert into \"note\"(\"name\") values (?) returning \"id\", \"created_at\"",
+            squash(&seg.seg)
+        );
+
+        let seg = SqlUpdater::new("note")
+            .set("name", "b")
+            .r#where(Wheres::equal("id", 1))
+            .returning(["*"])
+            .into_sql_seg(DbType::Postgres)
+            .unwrap();
+        assert_eq!(
+            "update \"note\" set \"name\" = $1 where id = $2 returning *",
+            squash(&seg.seg)
+        );
+
+        let seg = SqlDeleter::new("note")
+            .r#where(Wheres::equal("id", 1))
+            .returning(["id"])
+            .into_sql_seg(DbType::Postgres)
+            .unwrap();
+        assert_eq!(
+            "delete from \"note\" where id = $1 returning \"id\"",
+            squash(&seg.seg)
+        );
     }
 }
@@ -0,0 +1,199 @@
+use crate::{ChinSqlError, DbType, SqlSeg, SqlValue, place_hoder::PlaceHolderStyle};
+
+impl SqlSeg<'_> {
+    /// The statement with whitespace runs outside of literals collapsed and
+    /// commas followed by one space, e.g. `select  a ,b  from "note" `
+    /// becomes `select a, b from "note"`.
+    pub fn pretty(&self) -> String {
+        let mut out = String::with_capacity(self.seg.len());
+        scan(&self.seg, |token| match token {
+            Token::Space => {
+                if !out.is_empty() && !out.ends_with(' ') && !out.ends_with('(') {
+                    out.push(' ');
+                }
+            }
+            Token::Char(')') => {
+                if out.ends_with(' ') {
+                    out.pop();
+                }
+                out.push(')');
+            }
+            Token::Char(',') => {
+                if out.ends_with(' ') {
+                    out.pop();
+                }
+                out.push_str(", ");
+            }
+            Token::Char(c) => out.push(c),
+            Token::Quoted(s) => out.push_str(s),
+        });
+        if out.ends_with(' ') {
+            out.pop();
+        }
+        out
+    }
+
+    /// The pretty statement with every placeholder replaced by its value as
+    /// a literal of `db_type`, only meant for logs and debugging.
+    pub fn render_inline(&self, db_type: DbType) -> Result<String, ChinSqlError> {
+        let pretty = self.pretty();
+        let mut out = String::with_capacity(pretty.len());
+        let mut next = 0;
+        let mut named: Vec<(String, usize)> = vec![];
+        let mut chars = pretty.chars().peekable();
+        let mut quote = None;
+        let (numbered, named_colon) = match db_type.dialect().placeholder().style() {
+            PlaceHolderStyle::DollarNumber(_) => (true, false),
+            PlaceHolderStyle::QustionMark => (false, true),
+            PlaceHolderStyle::Positional => (false, false),
+        };
+
+        while let Some(c) = chars.next() {
+            match (quote, c) {
+                (Some(q), c) => {
+                    if c == q {
+                        quote = None;
+                    }
+                    out.push(c);
+                }
+                (None, '\'' | '"') => {
+                    quote = Some(c);
+                    out.push(c);
+                }
+                (None, '?') if !numbered => {
+                    out.push_str(&self.literal(next, "?", db_type)?);
+                    next += 1;
+                }
+                (None, ':')
+                    if named_colon
+                        && chars
+                            .peek()
+                            .is_some_and(|d| d.is_ascii_alphabetic() || *d == '_') =>
+                {
+                    // a name takes the next index on its first appearance
+                    let mut name = String::new();
+                    while let Some(d) = chars.next_if(|d| d.is_ascii_alphanumeric() || *d == '_') {
+                        name.push(d);
+                    }
+                    let index = match named.iter().find(|(n, _)| *n == name) {
+                        Some((_, index)) => *index,
+                        None => {
+                            named.push((name.clone(), next));
+                            next += 1;
+                            next - 1
+                        }
+                    };
+                    out.push_str(&self.literal(index, &format!(":{name}"), db_type)?);
+                }
+                (None, '$') if numbered => {
+                    let mut num = String::new();
+                    while let Some(d) = chars.next_if(|d| d.is_ascii_digit()) {
+                        num.push(d);
+                    }
+                    let placeholder = format!("${num}");
+                    let index = num
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|n| n.checked_sub(1))
+                        .ok_or_else(|| ChinSqlError::UnknownPlaceholder(placeholder.clone()))?;
+                    out.push_str(&self.literal(index, &placeholder, db_type)?);
+                }
+                (None, c) => out.push(c),
+            }
+        }
+
+        Ok(out)
+    }
+
+    fn literal(
+        &self,
+        index: usize,
+        placeholder: &str,
+        db_type: DbType,
+    ) -> Result<String, ChinSqlError> {
+        self.values
+            .get(index)
+            .map(|value| value.to_literal(db_type))
+            .ok_or_else(|| ChinSqlError::UnknownPlaceholder(placeholder.to_owned()))
+    }
+}
+
+impl SqlValue<'_> {
+    /// The value as a SQL literal, encoded the same way it is bound.
+    pub fn to_literal(&self, db_type: DbType) -> String {
+        db_type.dialect().literal(self)
+    }
+}
+
+enum Token<'s> {
+    Space,
+    Char(char),
+    /// A string literal or quoted identifier, kept untouched.
+    Quoted(&'s str),
+}
+
+fn scan<'s, F: FnMut(Token<'s>)>(sql: &'s str, mut f: F) {
+    let mut iter = sql.char_indices();
+    while let Some((start, c)) = iter.next() {
+        match c {
+            '\'' | '"' => {
+                // a doubled quote inside the literal ends one part and
+                // starts the next, which joins back to the same text
+                let end = iter
+                    .find(|(_, e)| *e == c)
+                    .map(|(i, _)| i + 1)
+                    .unwrap_or(sql.len());
+                f(Token::Quoted(&sql[start..end]));
+            }
+            c if c.is_whitespace() => f(Token::Space),
+            c => f(Token::Char(c)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::{DbType, IntoSqlSeg, PlaceHolderType, SqlInserter, SqlSeg, SqlValue, Wheres};
+
+    #[test]
+    fn pretty() {
+        let seg = SqlSeg::of(" select  \"a  b\" ,  'x  y'  from ( select  1 )  ", vec![]);
+        assert_eq!("select \"a  b\", 'x  y' from (select 1)", seg.pretty());
+    }
+
+    #[test]
+    fn render_inline() {
+        let time = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        let inserter = || {
+            SqlInserter::new("note")
+                .field("name", "it's")
+                .field("done", true)
+                .field("data", SqlValue::Blob(vec![0xca, 0xfe].into()))
+                .field("at", time)
+                .field("score", None::<i64>)
+        };
+
+        let seg = inserter().into_sql_seg(DbType::Postgres).unwrap();
+        assert_eq!(
+            "insert into \"note\"(\"name\", \"done\", \"data\", \"at\", \"score\") values \
+             ('it''s', true, '\\xcafe'::bytea, '2024-01-02T03:04:05+00:00'::timestamptz, null)",
+            seg.render_inline(DbType::Postgres).unwrap()
+        );
+
+        let seg = inserter().into_sql_seg(DbType::Sqlite).unwrap();
+        let sql = seg.render_inline(DbType::Sqlite).unwrap();
+        assert!(sql.starts_with("insert into \"note\"(\"name\", \"done\", \"data\", \"at\", \"score\") values ('it''s', 1, x'cafe', "));
+        assert!(sql.ends_with(" /* 2024-01-02T03:04:05+00:00 */, null)"));
+
+        let seg = Wheres::equal("name", "?")
+            .build(DbType::Sqlite, &mut PlaceHolderType::question_mark())
+            .unwrap()
+            .unwrap();
+        assert_eq!("name = '?'", seg.render_inline(DbType::Sqlite).unwrap());
+
+        let seg = SqlSeg::of("a = $2", vec![SqlValue::I64(1)]);
+        assert!(seg.render_inline(DbType::Postgres).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use crate::{ChinSqlError, SqlTypedField, SqlValue, SqlValueRow, SqlValueStatic};
+
+pub trait FromSqlRow: Sized {
+    fn from_sql_row(row: &SqlValueRow) -> Result<Self, ChinSqlError>;
+}
+
+impl SqlValueRow {
+    pub fn get<T>(&self, column: &str) -> Result<T, ChinSqlError>
+    where
+        T: TryFrom<SqlValueStatic, Error = ChinSqlError>,
+    {
+        T::try_from(self.value(column)?.clone()).map_err(|err| ChinSqlError::Column {
+            column: column.to_owned(),
+            source: Box::new(err),
+        })
+    }
+
+    pub fn value(&self, column: &str) -> Result<&SqlValueStatic, ChinSqlError> {
+        self.row
+            .get(column)
+            .ok_or_else(|| ChinSqlError::MissingColumn(column.to_owned()))
+    }
+
+    pub fn decode<T: FromSqlRow>(&self) -> Result<T, ChinSqlError> {
+        T::from_sql_row(self)
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for SqlValueRow
+where
+    K: Into<Arc<str>>,
+    V: Into<SqlValueStatic>,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self {
+            row: iter
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }
+    }
+}
+
+impl<'a, T> SqlTypedField<'a, T>
+where
+    T: TryFrom<SqlValue<'static>, Error = ChinSqlError>,
+{
+    /// Reads this field back from a row, by its alias if it has one.
+    pub fn decode(&self, row: &SqlValueRow) -> Result<T, ChinSqlError> {
+        row.get(self.alias.unwrap_or(self.field_name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChinSqlError, FromSqlRow, SqlTypedField, SqlValue, SqlValueRow, str_type::Text};
+
+    struct Note {
+        id: i64,
+        title: Text,
+        score: Option<f64>,
+    }
+
+    impl FromSqlRow for Note {
+        fn from_sql_row(row: &SqlValueRow) -> Result<Self, ChinSqlError> {
+            Ok(Self {
+                id: row.get("id")?,
+                title: row.get("title")?,
+                score: row.get("score")?,
+            })
+        }
+    }
+
+    fn row() -> SqlValueRow {
+        [
+            ("id", SqlValue::I64(1)),
+            ("title", SqlValue::Str("hello".into())),
+            ("score", SqlValue::NullUnknown),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn decode_row() {
+        let note: Note = row().decode().unwrap();
+        assert_eq!(1, note.id);
+        assert_eq!("hello", note.title.as_str());
+        assert_eq!(None, note.score);
+
+        let title = SqlTypedField::<Text>::new("n", "title").with_alias("id");
+        assert!(title.decode(&row()).is_err());
+    }
+
+    #[test]
+    fn missing_column() {
+        let err = row().get::<i64>("body").unwrap_err();
+        assert!(err.to_string().contains("`body`"));
+        assert_eq!(Some("body"), err.column());
+
+        let err = row().get::<i64>("title").unwrap_err();
+        assert_eq!(Some("title"), err.column());
+        let ChinSqlError::Column { source, .. } = err else {
+            panic!("unexpected error {err:?}");
+        };
+        assert!(matches!(
+            *source,
+            ChinSqlError::UnexpectedType {
+                expected: "i64",
+                found: "Str"
+            }
+        ));
+    }
+}
@@ -1,11 +1,17 @@
-use crate::{ChinSqlError, DbType, IntoSqlSeg};
+use crate::{
+    ChinSqlError, DbType, DryRun, Froms, Ident, IntoSqlSeg, SqlBuilder, SqlExpr, guard_filter,
+    returning_clause,
+};
 
 use super::{SqlSeg, place_hoder::PlaceHolderType, sql_value::SqlValue, wheres::Wheres};
 
 pub struct SqlUpdater<'a> {
     table: &'a str,
-    setters: Vec<(&'a str, SqlValue<'a>)>,
+    setters: Vec<(&'a str, SqlExpr<'a>)>,
+    from: Option<Froms<'a>>,
     wheres: Wheres<'a>,
+    returning: Vec<&'a str>,
+    all_rows: bool,
 }
 
 impl<'a> SqlUpdater<'a> {
@@ -13,13 +19,16 @@ impl<'a> SqlUpdater<'a> {
         SqlUpdater {
             table,
             setters: vec![],
+            from: None,
             wheres: Wheres::and([]),
+            returning: vec![],
+            all_rows: false,
         }
     }
 
     pub fn set_if_some<T: Into<SqlValue<'a>>>(mut self, key: &'a str, value: Option<T>) -> Self {
         if let Some(v) = value {
-            self.setters.push((key, v.into()));
+            self.setters.push((key, SqlExpr::Value(v.into())));
         }
 
         self
@@ -32,14 +41,28 @@ impl<'a> SqlUpdater<'a> {
         trans: F,
     ) -> Self {
         if let Some(v) = value {
-            self.setters.push((key, trans(v).into()));
+            self.setters.push((key, SqlExpr::Value(trans(v).into())));
         }
 
         self
     }
 
     pub fn set<T: Into<SqlValue<'a>>>(mut self, key: &'a str, v: T) -> Self {
-        self.setters.push((key, v.into()));
+        self.setters.push((key, SqlExpr::Value(v.into())));
+        self
+    }
+
+    /// Set a column to an expression, e.g.
+    /// `SqlExpr::col("counter") + SqlValue::I64(1)` or a scalar subquery.
+    pub fn set_expr<T: Into<SqlExpr<'a>>>(mut self, key: &'a str, expr: T) -> Self {
+        self.setters.push((key, expr.into()));
+        self
+    }
+
+    /// Join other tables with `update ... from ...`, the join conditions go
+    /// to `where`. Needs SQLite 3.33 or later.
+    pub fn from(mut self, from: Froms<'a>) -> Self {
+        self.from.replace(from);
         self
     }
 
@@ -47,46 +70,191 @@ impl<'a> SqlUpdater<'a> {
         self.wheres = wheres;
         self
     }
-}
 
-impl<'a> IntoSqlSeg<'a> for SqlUpdater<'a> {
-    fn into_sql_seg2(
+    /// Columns of the affected rows to return, `*` for all of them.
+    pub fn returning<T: Into<Vec<&'a str>>>(mut self, columns: T) -> Self {
+        self.returning = columns.into();
+        self
+    }
+
+    /// Run without a filter on purpose, otherwise a filter which collapses
+    /// to nothing is refused with `ChinSqlError::UnfilteredWrite`.
+    pub fn all_rows(mut self) -> Self {
+        self.all_rows = true;
+        self
+    }
+
+    /// Render the statement without refusing a missing filter, to see what
+    /// would be executed.
+    pub fn dry_run(self, db_type: DbType) -> Result<DryRun<'a>, ChinSqlError> {
+        let mut pht = db_type.dialect().placeholder();
+        self.render(db_type, &mut pht, true)
+    }
+
+    fn render(
         self,
         db_type: DbType,
         pht: &mut PlaceHolderType,
-    ) -> Result<SqlSeg<'a>, ChinSqlError> {
+        dry_run: bool,
+    ) -> Result<DryRun<'a>, ChinSqlError> {
         if self.setters.is_empty() {
-            return Err(ChinSqlError::BuilderSqlError("update setters is empty".to_owned()));
+            return Err(ChinSqlError::EmptyBuilder {
+                builder: "SqlUpdater",
+                table: self.table.to_owned(),
+                reason: "no column to set",
+            });
         }
 
         let mut sb = String::new();
         let mut values: Vec<SqlValue<'a>> = Vec::new();
 
+        let (table_list, from) = match self.from {
+            Some(from) if db_type.dialect().update_from_in_table_list() => (Some(from), None),
+            from => (None, from),
+        };
+
         sb.push_str(" update ");
-        sb.push_str(self.table);
+        sb.push_str(&Ident::new(self.table).quote(db_type)?);
+        if let Some(from) = table_list {
+            let SqlSeg { seg, values: v } =
+                SqlBuilder::new().merge(from).into_sql_seg2(db_type, pht)?;
+            sb.push_str(", ");
+            sb.push_str(&seg);
+            values.extend(v);
+        }
         sb.push_str(" set ");
 
-        let fields: Vec<String> = self
-            .setters
-            .into_iter()
-            .map(|(key, v)| {
-                values.push(v);
-                format!(" {} = {} ", key, pht.next_ph())
-            })
-            .collect();
+        let mut fields: Vec<String> = Vec::with_capacity(self.setters.len());
+        for (key, expr) in self.setters {
+            let key = Ident::new(key).quote(db_type)?;
+            let expr = expr.render(db_type, pht, &mut values)?;
+            fields.push(format!(" {key} = {expr} "));
+        }
         sb.push_str(fields.join(", ").as_str());
 
-        if let Some(filters) = self.wheres.build(db_type, pht) {
+        if let Some(from) = from {
+            let SqlSeg { seg, values: v } =
+                SqlBuilder::new().merge(from).into_sql_seg2(db_type, pht)?;
+            sb.push_str(" from ");
+            sb.push_str(&seg);
+            values.extend(v);
+        }
+
+        let filters = guard_filter(
+            self.wheres.build(db_type, pht)?,
+            "SqlUpdater",
+            self.table,
+            self.all_rows || dry_run,
+        )?;
+        let all_rows = filters.is_none();
+        if let Some(filters) = filters {
             sb.push_str(" where ");
             sb.push_str(filters.seg.as_str());
 
             values.extend(filters.values);
-        } else {
-            Err(ChinSqlError::FilterBuildError(
-                "filter_is_empty".to_string(),
-            ))?
         }
 
-        Ok(SqlSeg::of(sb, values))
+        sb.push_str(&returning_clause(&self.returning, db_type)?);
+
+        Ok(DryRun {
+            seg: SqlSeg::of(sb, values),
+            all_rows,
+        })
+    }
+}
+
+impl<'a> IntoSqlSeg<'a> for SqlUpdater<'a> {
+    fn into_sql_seg2(
+        self,
+        db_type: DbType,
+        pht: &mut PlaceHolderType,
+    ) -> Result<SqlSeg<'a>, ChinSqlError> {
+        self.render(db_type, pht, false).map(|e| e.seg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ChinSqlError, DbType, Froms, IntoSqlSeg, SqlExpr, SqlReader, SqlUpdater, SqlValue, Wheres,
+        test_util::squash,
+    };
+
+    #[test]
+    fn expression_setters() {
+        let total = SqlReader::builder(
+            [SqlExpr::count_all()],
+            Froms::Table {
+                table_name: "comment",
+                alias: "c",
+            },
+        )
+        .wheres(Wheres::Raw("c.note_id = note.id".into()))
+        .build();
+
+        let seg = SqlUpdater::new("note")
+            .set("name", "a")
+            .set_expr("counter", SqlExpr::col("counter") + SqlValue::I64(1))
+            .set_expr("updated_at", SqlExpr::func("now", []))
+            .set_expr("comments", SqlExpr::sub(total))
+            .set_if_some("title", None::<&str>)
+            .r#where(Wheres::equal("id", 1))
+            .into_sql_seg(DbType::Postgres)
+            .unwrap();
+        assert_eq!(
+            "update \"note\" set \"name\" = $1 , \"counter\" = (\"counter\" + $2) , \
+             \"updated_at\" = now() , \"comments\" = (select count(*) from \"comment\" as \"c\" \
+             where c.note_id = note.id) where id = $3",
+            squash(&seg.seg)
+        );
+        assert_eq!(3, seg.values.len());
+    }
+
+    #[test]
+    fn update_from() {
+        let seg = SqlUpdater::new("note")
+            .set_expr("author", SqlExpr::raw(["u.name".into()]))
+            .from(Froms::Table {
+                table_name: "user",
+                alias: "u",
+            })
+            .r#where(Wheres::and([
+                Wheres::Raw("u.id = note.user_id".into()),
+                Wheres::equal("u.active", true),
+            ]))
+            .into_sql_seg(DbType::Sqlite)
+            .unwrap();
+        assert_eq!(
+            "update \"note\" set \"author\" = u.name from \"user\" as \"u\" \
+             where u.id = note.user_id and u.active = ?",
+            squash(&seg.seg)
+        );
+    }
+
+    #[test]
+    fn unfiltered_update() {
+        let updater = || {
+            SqlUpdater::new("note")
+                .set("archived", true)
+                .r#where(Wheres::if_some(None::<i64>, |id| Wheres::equal("id", id)))
+        };
+
+        assert!(matches!(
+            updater().into_sql_seg(DbType::Sqlite),
+            Err(ChinSqlError::UnfilteredWrite {
+                builder: "SqlUpdater",
+                ..
+            })
+        ));
+
+        let dry_run = updater().dry_run(DbType::Sqlite).unwrap();
+        assert!(dry_run.all_rows);
+        assert_eq!(
+            "update \"note\" set \"archived\" = ?",
+            squash(&dry_run.seg.seg)
+        );
+
+        let seg = updater().all_rows().into_sql_seg(DbType::Sqlite).unwrap();
+        assert_eq!("update \"note\" set \"archived\" = ?", squash(&seg.seg));
     }
 }
@@ -0,0 +1,163 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, FixedOffset, Utc};
+
+use super::{SqlValue, sqlite::sqltype::Timestamptz};
+use crate::ChinSqlError;
+
+/// A homogeneous list bound as one parameter, a Postgres array or a JSON
+/// array text on SQLite.
+#[derive(Clone, Debug)]
+pub enum SqlArray<'a> {
+    Bool(Vec<bool>),
+    I16(Vec<i16>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
+    F64(Vec<f64>),
+    Str(Vec<Cow<'a, str>>),
+    FixedOffset(Vec<DateTime<FixedOffset>>),
+    Utc(Vec<DateTime<Utc>>),
+}
+
+macro_rules! collect_array {
+    ($values:expr, $variant:ident) => {
+        $values
+            .into_iter()
+            .map(|e| match e {
+                SqlValue::$variant(v) => Ok(v),
+                other => Err(ChinSqlError::UnexpectedType {
+                    expected: stringify!($variant),
+                    found: other.type_name(),
+                }),
+            })
+            .collect::<Result<Vec<_>, ChinSqlError>>()
+            .map(SqlArray::$variant)
+    };
+}
+
+impl<'a> SqlArray<'a> {
+    pub fn len(&self) -> usize {
+        match self {
+            SqlArray::Bool(v) => v.len(),
+            SqlArray::I16(v) => v.len(),
+            SqlArray::I32(v) => v.len(),
+            SqlArray::I64(v) => v.len(),
+            SqlArray::F64(v) => v.len(),
+            SqlArray::Str(v) => v.len(),
+            SqlArray::FixedOffset(v) => v.len(),
+            SqlArray::Utc(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn live_static(self) -> SqlArray<'static> {
+        match self {
+            SqlArray::Bool(v) => SqlArray::Bool(v),
+            SqlArray::I16(v) => SqlArray::I16(v),
+            SqlArray::I32(v) => SqlArray::I32(v),
+            SqlArray::I64(v) => SqlArray::I64(v),
+            SqlArray::F64(v) => SqlArray::F64(v),
+            SqlArray::Str(v) => {
+                SqlArray::Str(v.into_iter().map(|e| Cow::Owned(e.into_owned())).collect())
+            }
+            SqlArray::FixedOffset(v) => SqlArray::FixedOffset(v),
+            SqlArray::Utc(v) => SqlArray::Utc(v),
+        }
+    }
+
+    /// JSON text as `json_each` reads it, timestamps are encoded the same
+    /// way they are stored on SQLite.
+    pub fn to_json(&self) -> String {
+        fn join<T, F: Fn(&T) -> String>(vs: &[T], f: F) -> String {
+            format!("[{}]", vs.iter().map(f).collect::<Vec<String>>().join(","))
+        }
+
+        match self {
+            SqlArray::Bool(v) => join(v, |e| e.to_string()),
+            SqlArray::I16(v) => join(v, |e| e.to_string()),
+            SqlArray::I32(v) => join(v, |e| e.to_string()),
+            SqlArray::I64(v) => join(v, |e| e.to_string()),
+            SqlArray::F64(v) => join(v, |e| {
+                if e.is_finite() {
+                    e.to_string()
+                } else {
+                    "null".to_owned()
+                }
+            }),
+            SqlArray::Str(v) => join(v, |e| json_string(e)),
+            SqlArray::FixedOffset(v) => join(v, |e| i64::from(Timestamptz::from(*e)).to_string()),
+            SqlArray::Utc(v) => join(v, |e| i64::from(Timestamptz::from(*e)).to_string()),
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl<'a> TryFrom<Vec<SqlValue<'a>>> for SqlArray<'a> {
+    type Error = ChinSqlError;
+
+    /// The element type is taken from the first value, every other value
+    /// must have the same type.
+    fn try_from(values: Vec<SqlValue<'a>>) -> Result<Self, Self::Error> {
+        match values.first() {
+            Some(SqlValue::Bool(_)) => collect_array!(values, Bool),
+            Some(SqlValue::I16(_)) => collect_array!(values, I16),
+            Some(SqlValue::I32(_)) => collect_array!(values, I32),
+            Some(SqlValue::I64(_)) => collect_array!(values, I64),
+            Some(SqlValue::F64(_)) => collect_array!(values, F64),
+            Some(SqlValue::Str(_)) => collect_array!(values, Str),
+            Some(SqlValue::FixedOffset(_)) => collect_array!(values, FixedOffset),
+            Some(SqlValue::Utc(_)) => collect_array!(values, Utc),
+            Some(other) => Err(ChinSqlError::UnexpectedType {
+                expected: "array element",
+                found: other.type_name(),
+            }),
+            None => Err(ChinSqlError::EmptyArray),
+        }
+    }
+}
+
+impl<'a> From<SqlArray<'a>> for SqlValue<'a> {
+    fn from(value: SqlArray<'a>) -> Self {
+        SqlValue::Array(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChinSqlError, SqlArray, SqlValue};
+
+    #[test]
+    fn array_from_values() {
+        let arr = SqlArray::try_from(vec![SqlValue::I64(1), SqlValue::I64(2)]).unwrap();
+        assert_eq!("[1,2]", arr.to_json());
+
+        let arr = SqlArray::try_from(vec![SqlValue::from("a\"b"), SqlValue::from("c\\")]).unwrap();
+        assert_eq!(r#"["a\"b","c\\"]"#, arr.to_json());
+
+        assert!(SqlArray::try_from(vec![SqlValue::I64(1), SqlValue::from("1")]).is_err());
+        assert!(matches!(
+            SqlArray::try_from(vec![]),
+            Err(ChinSqlError::EmptyArray)
+        ));
+    }
+}
@@ -0,0 +1,117 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{ChinSqlError, LogicFieldType, SqlValue};
+
+/// A column holding `T` as JSON, `TEXT` on SQLite and `JSONB` on Postgres.
+/// Query into it with [`Wheres::json_path`] and [`Wheres::json_contains`].
+///
+/// [`Wheres::json_path`]: crate::Wheres::json_path
+/// [`Wheres::json_contains`]: crate::Wheres::json_contains
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Json<T>(pub T);
+
+/// Serialized JSON text, the payload of [`SqlValue::Json`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonText<'a>(pub Cow<'a, str>);
+
+impl<T> Json<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Serialize> Json<T> {
+    pub fn to_sql_value<'a>(&self) -> Result<SqlValue<'a>, ChinSqlError> {
+        serde_json::to_string(&self.0)
+            .map(|s| SqlValue::Json(JsonText(s.into())))
+            .map_err(|err| ChinSqlError::TransformError(format!("unable to serialize json: {err}")))
+    }
+}
+
+impl<T> std::ops::Deref for Json<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> std::ops::DerefMut for Json<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for Json<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<'a> JsonText<'a> {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a, T: Serialize> TryFrom<&Json<T>> for SqlValue<'a> {
+    type Error = ChinSqlError;
+
+    fn try_from(value: &Json<T>) -> Result<Self, Self::Error> {
+        value.to_sql_value()
+    }
+}
+
+impl<'a, T: Serialize> TryFrom<Json<T>> for SqlValue<'a> {
+    type Error = ChinSqlError;
+
+    fn try_from(value: Json<T>) -> Result<Self, Self::Error> {
+        value.to_sql_value()
+    }
+}
+
+impl<'a, T: Serialize> TryFrom<Option<Json<T>>> for SqlValue<'a> {
+    type Error = ChinSqlError;
+
+    fn try_from(value: Option<Json<T>>) -> Result<Self, Self::Error> {
+        match value {
+            Some(v) => v.to_sql_value(),
+            None => Ok(SqlValue::Null(LogicFieldType::Json)),
+        }
+    }
+}
+
+impl<'a, T: DeserializeOwned> TryFrom<SqlValue<'a>> for Json<T> {
+    type Error = ChinSqlError;
+
+    /// SQLite hands the column back as plain text, so `Str` is parsed too.
+    fn try_from(value: SqlValue<'a>) -> Result<Self, Self::Error> {
+        let text = match &value {
+            SqlValue::Json(v) => v.as_str(),
+            SqlValue::Str(v) => v.as_ref(),
+            other => {
+                return Err(ChinSqlError::UnexpectedType {
+                    expected: "Json",
+                    found: other.type_name(),
+                });
+            }
+        };
+        serde_json::from_str(text)
+            .map(Json)
+            .map_err(|err| ChinSqlError::TransformError(format!("unable to parse json: {err}")))
+    }
+}
+
+impl<'a, T: DeserializeOwned> TryFrom<SqlValue<'a>> for Option<Json<T>> {
+    type Error = ChinSqlError;
+
+    fn try_from(value: SqlValue<'a>) -> Result<Self, Self::Error> {
+        match value {
+            SqlValue::Null(_) | SqlValue::NullUnknown => Ok(None),
+            other => Ok(Some(other.try_into()?)),
+        }
+    }
+}
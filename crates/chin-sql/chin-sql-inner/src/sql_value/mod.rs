@@ -1,19 +1,24 @@
 #[cfg(feature = "sqlite")]
-mod sqlite;
+pub(crate) mod sqlite;
 
 #[cfg(feature = "postgres")]
 mod postgres;
 
 use std::{borrow::Cow, collections::HashMap, sync::Arc};
 
+mod array;
+pub mod json_type;
 pub mod str_type;
 pub mod time_type;
 
+pub use array::SqlArray;
+
 use chrono::{DateTime, FixedOffset, Utc};
 use sqlite::sqltype::Timestamptz;
 
 use crate::{
     ChinSqlError, LogicFieldType,
+    json_type::JsonText,
     str_type::{Text, Varchar},
     time_type::TID,
 };
@@ -30,6 +35,9 @@ pub enum SqlValue<'a> {
     FixedOffset(DateTime<FixedOffset>),
     Utc(DateTime<Utc>),
     Blob(Cow<'a, [u8]>),
+    Array(SqlArray<'a>),
+    /// Serialized JSON, see [`json_type::Json`].
+    Json(JsonText<'a>),
     Null(LogicFieldType),
     NullUnknown,
 }
@@ -42,6 +50,25 @@ pub struct SqlValueRow {
 }
 
 impl<'a> SqlValue<'a> {
+    /// Name of the variant, used in conversion errors.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            SqlValue::Bool(_) => "Bool",
+            SqlValue::I8(_) => "I8",
+            SqlValue::I16(_) => "I16",
+            SqlValue::I32(_) => "I32",
+            SqlValue::I64(_) => "I64",
+            SqlValue::F64(_) => "F64",
+            SqlValue::Str(_) => "Str",
+            SqlValue::FixedOffset(_) => "FixedOffset",
+            SqlValue::Utc(_) => "Utc",
+            SqlValue::Blob(_) => "Blob",
+            SqlValue::Array(_) => "Array",
+            SqlValue::Json(_) => "Json",
+            SqlValue::Null(_) | SqlValue::NullUnknown => "Null",
+        }
+    }
+
     pub fn live_static(self) -> SqlValue<'static> {
         match self {
             SqlValue::I8(v) => SqlValue::I8(v),
@@ -54,8 +81,10 @@ impl<'a> SqlValue<'a> {
             SqlValue::F64(v) => SqlValue::F64(v),
             SqlValue::Blob(cow) => SqlValue::Blob(Cow::Owned(cow.to_vec())),
             SqlValue::Str(cow) => SqlValue::Str(Cow::Owned(cow.into_owned())),
+            SqlValue::Array(arr) => SqlValue::Array(arr.live_static()),
+            SqlValue::Json(v) => SqlValue::Json(JsonText(Cow::Owned(v.0.into_owned()))),
             SqlValue::Null(logic_field_type) => SqlValue::Null(logic_field_type),
-            SqlValue::NullUnknown => unreachable!(),
+            SqlValue::NullUnknown => SqlValue::NullUnknown,
         }
     }
 }
@@ -120,12 +149,24 @@ impl<'a> From<DateTime<FixedOffset>> for SqlValue<'a> {
     }
 }
 
+impl<'a> From<DateTime<Utc>> for SqlValue<'a> {
+    fn from(val: DateTime<Utc>) -> Self {
+        SqlValue::Utc(val)
+    }
+}
+
 impl<'a> From<f64> for SqlValue<'a> {
     fn from(val: f64) -> Self {
         SqlValue::F64(val)
     }
 }
 
+impl<'a> From<f32> for SqlValue<'a> {
+    fn from(val: f32) -> Self {
+        SqlValue::F64(val.into())
+    }
+}
+
 impl<const LIMIT: usize> From<Varchar<LIMIT>> for SqlValue<'_> {
     fn from(value: Varchar<LIMIT>) -> Self {
         Self::Str(Cow::Owned(value.0.to_string()))
@@ -138,6 +179,14 @@ impl From<Text> for SqlValue<'_> {
     }
 }
 
+/// Pins the error type of the conversion closures below.
+fn convert<A, B, F>(v: A, conv: F) -> Result<B, ChinSqlError>
+where
+    F: FnOnce(A) -> Result<B, ChinSqlError>,
+{
+    conv(v)
+}
+
 macro_rules! try_from_sql_value {
     ($tp:ty, $rlt:expr, $($variant:ident => $conv:expr),*) => {
         impl<'a> TryFrom<SqlValue<'a>> for $tp {
@@ -146,11 +195,12 @@ macro_rules! try_from_sql_value {
             fn try_from(value: SqlValue<'a>) -> Result<Self, Self::Error> {
                 match value {
                     $(
-                        SqlValue::$variant(v) => Ok($conv(v)?),
+                        SqlValue::$variant(v) => convert(v, $conv),
                     )*
-                    _ => Err(ChinSqlError::TransformError(
-                        concat!("Unable to transform to ", stringify!($tp)).to_owned(),
-                    )),
+                    other => Err(ChinSqlError::UnexpectedType {
+                        expected: stringify!($tp),
+                        found: other.type_name(),
+                    }),
                 }
             }
         }
@@ -167,11 +217,12 @@ macro_rules! try_from_sql_value {
                         Ok(None)
                     },
                     $(
-                        SqlValue::$variant(v) => Ok(Some($conv(v)?)),
+                        SqlValue::$variant(v) => convert(v, $conv).map(Some),
                     )*
-                    _ => Err(ChinSqlError::TransformError(
-                        concat!("Unable to transform ", stringify!($tp)).to_owned(),
-                    )),
+                    other => Err(ChinSqlError::UnexpectedType {
+                        expected: stringify!($tp),
+                        found: other.type_name(),
+                    }),
                 }
             }
         }
@@ -206,13 +257,25 @@ try_from_sql_value!(DateTime<FixedOffset>, LogicFieldType::Timestamptz,
     FixedOffset => |v: DateTime<FixedOffset>| Ok(v),
     I64 => |v: i64| Timestamptz::try_from(v).map(|tz| *tz)
 );
+try_from_sql_value!(DateTime<Utc>, LogicFieldType::Timestamp,
+    Utc => |v: DateTime<Utc>| Ok(v),
+    FixedOffset => |v: DateTime<FixedOffset>| Ok(v.to_utc()),
+    I64 => |v: i64| Timestamptz::try_from(v).map(|tz| tz.to_utc())
+);
 try_from_sql_value!(bool, LogicFieldType::Bool,
     Bool => |v: bool| Ok(v),
     I64 => |v: i64| Ok(v != 0)
 );
 try_from_sql_value!(i64, LogicFieldType::I64, I64 => |v: i64| Ok(v));
-try_from_sql_value!(i32, LogicFieldType::I32, I32 => |v: i32| Ok(v));
+try_from_sql_value!(i32, LogicFieldType::I32,
+    I32 => |v: i32| Ok(v),
+    I64 => |v: i64| i32::try_from(v).map_err(|_| ChinSqlError::OutOfRange {
+        target: "i32".to_owned(),
+        value: v.to_string(),
+    })
+);
 try_from_sql_value!(f64, LogicFieldType::F64, F64 => |v: f64| Ok(v));
+try_from_sql_value!(f32, LogicFieldType::F64, F64 => |v: f64| Ok(v as f32));
 try_from_sql_value!(Cow<'a, str>, LogicFieldType::Text, Str => |v: Cow<'a, str>| Ok(v));
 try_from_sql_value!(String, LogicFieldType::Text,
     Str => |v: Cow<'a, str>| Ok(v.to_string())
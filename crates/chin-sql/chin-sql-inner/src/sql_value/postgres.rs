@@ -1,7 +1,7 @@
 use chrono::{DateTime, FixedOffset, Utc};
 use postgres_types::ToSql;
 
-use crate::{LogicFieldType, SqlValue};
+use crate::{ChinSqlError, LogicFieldType, SqlArray, SqlValue, json_type::JsonText};
 
 impl<'a> From<&'a SqlValue<'a>> for &'a (dyn ToSql + Sync + Send) {
     fn from(val: &'a SqlValue<'a>) -> Self {
@@ -16,6 +16,17 @@ impl<'a> From<&'a SqlValue<'a>> for &'a (dyn ToSql + Sync + Send) {
             SqlValue::Bool(v) => v,
             SqlValue::F64(v) => v,
             SqlValue::Blob(cow) => cow,
+            SqlValue::Json(v) => v,
+            SqlValue::Array(arr) => match arr {
+                SqlArray::Bool(v) => v,
+                SqlArray::I16(v) => v,
+                SqlArray::I32(v) => v,
+                SqlArray::I64(v) => v,
+                SqlArray::F64(v) => v,
+                SqlArray::Str(v) => v,
+                SqlArray::FixedOffset(v) => v,
+                SqlArray::Utc(v) => v,
+            },
             SqlValue::Null(rust_field_type) => match rust_field_type {
                 LogicFieldType::Bool => &None::<bool>,
                 LogicFieldType::I8 => &None::<i8>,
@@ -28,16 +39,130 @@ impl<'a> From<&'a SqlValue<'a>> for &'a (dyn ToSql + Sync + Send) {
                 LogicFieldType::Timestamptz => &None::<DateTime<FixedOffset>>,
                 LogicFieldType::Timestamp => &None::<DateTime<Utc>>,
                 LogicFieldType::Varchar(_) => &None::<String>,
+                LogicFieldType::Json => &None::<JsonText<'static>>,
             },
             SqlValue::NullUnknown => unreachable!(),
         }
     }
 }
 
+mod to_sql {
+    use bytes::{BufMut, BytesMut};
+    use postgres_types::{IsNull, ToSql, Type, to_sql_checked};
+
+    use crate::json_type::JsonText;
+
+    /// Version of the binary `jsonb` format.
+    const JSONB_VERSION: u8 = 1;
+
+    impl ToSql for JsonText<'_> {
+        fn to_sql(
+            &self,
+            ty: &Type,
+            out: &mut BytesMut,
+        ) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+            if *ty == Type::JSONB {
+                out.put_u8(JSONB_VERSION);
+            }
+            out.put_slice(self.as_str().as_bytes());
+            Ok(IsNull::No)
+        }
+
+        fn accepts(ty: &Type) -> bool {
+            matches!(*ty, Type::JSON | Type::JSONB)
+        }
+
+        to_sql_checked!();
+    }
+}
+
+impl SqlValue<'static> {
+    /// Decodes a raw column of a scalar type, `None` being null. The
+    /// postgres error is kept as source.
+    pub fn from_postgres(
+        ty: &postgres_types::Type,
+        raw: Option<&[u8]>,
+    ) -> Result<Self, ChinSqlError> {
+        <Self as postgres_types::FromSql>::from_sql_nullable(ty, raw)
+            .map_err(ChinSqlError::Postgres)
+    }
+}
+
 pub mod from_sql {
-    use postgres_types::FromSql;
+    use std::error::Error;
+
+    use chrono::{DateTime, NaiveDateTime};
+    use postgres_types::{FromSql, Type};
+
+    use serde::de::DeserializeOwned;
+
+    use crate::{
+        SqlValue,
+        json_type::{Json, JsonText},
+        str_type::{Text, Varchar},
+    };
+
+    /// The JSON text of a `json` or `jsonb` column.
+    fn json_payload<'a>(
+        ty: &Type,
+        raw: &'a [u8],
+    ) -> Result<&'a [u8], Box<dyn Error + Sync + Send>> {
+        if *ty == Type::JSONB {
+            match raw.split_first() {
+                Some((1, rest)) => Ok(rest),
+                _ => Err("unsupported jsonb version".into()),
+            }
+        } else {
+            Ok(raw)
+        }
+    }
+
+    impl<'a> FromSql<'a> for SqlValue<'static> {
+        fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+            let value = match *ty {
+                Type::BOOL => SqlValue::Bool(bool::from_sql(ty, raw)?),
+                Type::CHAR => SqlValue::I8(i8::from_sql(ty, raw)?),
+                Type::INT2 => SqlValue::I16(i16::from_sql(ty, raw)?),
+                Type::INT4 => SqlValue::I32(i32::from_sql(ty, raw)?),
+                Type::INT8 => SqlValue::I64(i64::from_sql(ty, raw)?),
+                Type::FLOAT4 => SqlValue::F64(f32::from_sql(ty, raw)?.into()),
+                Type::FLOAT8 => SqlValue::F64(f64::from_sql(ty, raw)?),
+                Type::BYTEA => SqlValue::Blob(Vec::<u8>::from_sql(ty, raw)?.into()),
+                Type::TIMESTAMPTZ => SqlValue::FixedOffset(DateTime::from_sql(ty, raw)?),
+                Type::TIMESTAMP => SqlValue::Utc(NaiveDateTime::from_sql(ty, raw)?.and_utc()),
+                Type::JSON | Type::JSONB => {
+                    let text = std::str::from_utf8(json_payload(ty, raw)?)?;
+                    SqlValue::Json(JsonText(text.to_owned().into()))
+                }
+                _ if String::accepts(ty) => SqlValue::Str(String::from_sql(ty, raw)?.into()),
+                _ => return Err(format!("unsupported postgres type {ty}").into()),
+            };
+            Ok(value)
+        }
+
+        fn from_sql_null(_ty: &Type) -> Result<Self, Box<dyn Error + Sync + Send>> {
+            Ok(SqlValue::NullUnknown)
+        }
 
-    use crate::str_type::{Text, Varchar};
+        fn accepts(ty: &Type) -> bool {
+            String::accepts(ty)
+                || matches!(
+                    *ty,
+                    Type::BOOL
+                        | Type::CHAR
+                        | Type::INT2
+                        | Type::INT4
+                        | Type::INT8
+                        | Type::FLOAT4
+                        | Type::FLOAT8
+                        | Type::BYTEA
+                        | Type::TIMESTAMPTZ
+                        | Type::TIMESTAMP
+                        | Type::JSON
+                        | Type::JSONB
+                )
+        }
+    }
 
     impl<'a> FromSql<'a> for Text {
         fn from_sql(
@@ -69,4 +194,46 @@ pub mod from_sql {
             String::accepts(ty)
         }
     }
+
+    impl<'a, T: DeserializeOwned> FromSql<'a> for Json<T> {
+        fn from_sql(
+            ty: &postgres_types::Type,
+            raw: &'a [u8],
+        ) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+            Ok(Json(serde_json::from_slice(json_payload(ty, raw)?)?))
+        }
+
+        fn accepts(ty: &postgres_types::Type) -> bool {
+            matches!(
+                *ty,
+                postgres_types::Type::JSON | postgres_types::Type::JSONB
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use postgres_types::Type;
+
+    use crate::{ChinSqlError, SqlValue};
+
+    #[test]
+    fn from_postgres() {
+        let value = SqlValue::from_postgres(&Type::INT4, Some(&[0, 0, 0, 7])).unwrap();
+        assert!(matches!(value, SqlValue::I32(7)));
+        let value = SqlValue::from_postgres(&Type::TEXT, Some(b"abc")).unwrap();
+        assert!(matches!(value, SqlValue::Str(v) if v == "abc"));
+        let value = SqlValue::from_postgres(&Type::JSONB, Some(b"\x01{}")).unwrap();
+        assert!(matches!(value, SqlValue::Json(v) if v.as_str() == "{}"));
+        let value = SqlValue::from_postgres(&Type::INT8, None).unwrap();
+        assert!(matches!(value, SqlValue::NullUnknown));
+
+        let err = SqlValue::from_postgres(&Type::INT4, Some(&[0, 7])).unwrap_err();
+        assert!(matches!(err, ChinSqlError::Postgres(_)));
+        assert!(err.source().is_some());
+        assert!(SqlValue::from_postgres(&Type::POINT, Some(&[0; 16])).is_err());
+    }
 }
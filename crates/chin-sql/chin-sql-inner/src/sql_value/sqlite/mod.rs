@@ -1,11 +1,15 @@
+use std::collections::HashMap;
+
 use sqltype::Timestamptz;
 
 use rusqlite::{
-    ToSql,
-    types::{ToSqlOutput, Value},
+    Row, ToSql,
+    types::{ToSqlOutput, Value, ValueRef},
 };
 
-use super::{SqlValue, SqlValueStatic};
+use crate::ChinSqlError;
+
+use super::{SqlValue, SqlValueRow, SqlValueStatic};
 
 pub mod sqltype;
 
@@ -26,6 +30,8 @@ impl<'a> ToSql for SqlValue<'a> {
             SqlValue::Bool(v) => v.to_sql(),
             SqlValue::F64(v) => v.to_sql(),
             SqlValue::Blob(cow) => cow.to_sql(),
+            SqlValue::Array(arr) => Ok(ToSqlOutput::Owned(Value::Text(arr.to_json()))),
+            SqlValue::Json(v) => v.as_str().to_sql(),
             SqlValue::Null(_) => Ok(ToSqlOutput::Owned(Value::Null)),
             SqlValue::NullUnknown => Ok(ToSqlOutput::Owned(Value::Null)),
         }
@@ -47,6 +53,8 @@ impl<'a> From<SqlValue<'a>> for Value {
             }
             SqlValue::Utc(date_time) => Value::from(i64::from(Timestamptz::from(date_time))),
             SqlValue::Blob(v) => Value::from(v.to_vec()),
+            SqlValue::Array(arr) => Value::Text(arr.to_json()),
+            SqlValue::Json(v) => Value::Text(v.0.into_owned()),
             SqlValue::Null(_) => Value::Null,
             SqlValue::NullUnknown => Value::Null,
         }
@@ -65,6 +73,24 @@ impl From<Value> for SqlValueStatic {
     }
 }
 
+impl TryFrom<&Row<'_>> for SqlValueRow {
+    type Error = ChinSqlError;
+
+    /// Text columns must be valid UTF-8, the rusqlite error is kept as source.
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        let stmt = row.as_ref();
+        let mut cells = HashMap::with_capacity(stmt.column_count());
+        for idx in 0..stmt.column_count() {
+            let value = match row.get_ref(idx)? {
+                ValueRef::Text(_) => SqlValue::Str(row.get::<_, String>(idx)?.into()),
+                other => Value::from(other).into(),
+            };
+            cells.insert(stmt.column_name(idx)?.into(), value);
+        }
+        Ok(Self { row: cells })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use chrono::{DateTime, Local};
@@ -94,4 +120,274 @@ mod tests {
             DateTime::parse_from_str(&time, "%Y-%m-%dT%H:%M:%S%.f %z").unwrap()
         );
     }
+
+    #[test]
+    fn decode_row() {
+        use std::error::Error;
+
+        use crate::{ChinSqlError, SqlValueRow};
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let decode = |sql: &str| {
+            conn.query_row(sql, [], |row| Ok(SqlValueRow::try_from(row)))
+                .unwrap()
+        };
+
+        let row = decode("select 1 as id, 'a' as name, null as score").unwrap();
+        assert_eq!(1, row.get::<i64>("id").unwrap());
+        assert_eq!("a", row.get::<String>("name").unwrap());
+        assert_eq!(None, row.get::<Option<f64>>("score").unwrap());
+
+        let err = decode("select cast(x'ff' as text) as name").unwrap_err();
+        assert!(matches!(err, ChinSqlError::Sqlite(_)));
+        assert!(
+            err.source()
+                .is_some_and(|e| e.downcast_ref::<rusqlite::Error>().is_some())
+        );
+    }
+
+    #[test]
+    fn in_json_each() {
+        use crate::{DbType, PlaceHolderType, Wheres};
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute("create table note (id integer not null, name text)", [])
+            .unwrap();
+        for id in 0..2000 {
+            conn.execute("insert into note values(?, ?)", (id, format!("n{id}")))
+                .unwrap();
+        }
+
+        let ss = Wheres::and([
+            Wheres::r#in("id", (0..1500).collect::<Vec<i64>>()),
+            Wheres::in_array("name", vec!["n1", "n2", "n\"x"]),
+        ])
+        .build(DbType::Sqlite, &mut PlaceHolderType::question_mark())
+        .unwrap()
+        .unwrap();
+        let count: i64 = conn
+            .query_row(
+                &format!("select count(*) from note where {}", ss.seg),
+                rusqlite::params_from_iter(ss.values.iter()),
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(2, count);
+    }
+
+    #[test]
+    fn named_params() {
+        use crate::{DbType, IntoSqlSeg, SegOrVal, SqlBuilder, Wheres};
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute("create table note (id integer not null, score integer)", [])
+            .unwrap();
+        for id in 0..10 {
+            conn.execute("insert into note values(?, ?)", (id, id % 3))
+                .unwrap();
+        }
+
+        let ss = SqlBuilder::new()
+            .bind("score", 1)
+            .seg("select count(*) from note")
+            .r#where(Wheres::SOV(vec![
+                "(score = ".into(),
+                SegOrVal::named("score"),
+                " or id = ".into(),
+                SegOrVal::named("score"),
+                ") and id < ".into(),
+                SegOrVal::val(9),
+            ]))
+            .into_sql_seg(DbType::Sqlite)
+            .unwrap();
+        let count: i64 = conn
+            .query_row(
+                &ss.seg,
+                rusqlite::params_from_iter(ss.values.iter()),
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(3, count);
+        assert_eq!(
+            "select count(*) from note where (score = 1 or id = 1) and id < 9",
+            ss.render_inline(DbType::Sqlite).unwrap()
+        );
+    }
+
+    #[test]
+    fn ilike_escape() {
+        use crate::{DbType, ILikeType, IntoSqlSeg, SqlBuilder, Wheres};
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute("create table note (name text not null)", [])
+            .unwrap();
+        for name in ["Rate 100%", "rate 1000", "a_b", "axb", "Ärger"] {
+            conn.execute("insert into note values(?)", [name]).unwrap();
+        }
+
+        let count = |text: &str, exact| -> i64 {
+            let ss = SqlBuilder::new()
+                .seg("select count(*) from note")
+                .r#where(Wheres::ilike("name", text, exact))
+                .into_sql_seg(DbType::Sqlite)
+                .unwrap();
+            conn.query_row(
+                &ss.seg,
+                rusqlite::params_from_iter(ss.values.iter()),
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(1, count("0%", ILikeType::LeftFuzzy));
+        assert_eq!(2, count("RATE", ILikeType::RightFuzzy));
+        assert_eq!(1, count("A_B", ILikeType::Original));
+        assert_eq!(0, count("b!", ILikeType::Fuzzy));
+        assert_eq!(0, count("äR", ILikeType::RightFuzzy));
+
+        let unicode = SqlBuilder::new()
+            .seg("select count(*) from note")
+            .r#where(Wheres::unicode_ilike("name", "äR", ILikeType::RightFuzzy))
+            .into_sql_seg(DbType::Sqlite)
+            .unwrap();
+        assert!(conn.prepare(&unicode.seg).is_err());
+        conn.create_scalar_function(
+            "unicode_lower",
+            1,
+            rusqlite::functions::FunctionFlags::SQLITE_UTF8,
+            |ctx| Ok(ctx.get::<String>(0)?.to_lowercase()),
+        )
+        .unwrap();
+        let count: i64 = conn
+            .query_row(
+                &unicode.seg,
+                rusqlite::params_from_iter(unicode.values.iter()),
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(1, count);
+    }
+
+    #[test]
+    fn full_text() {
+        use crate::{
+            CreateTableField, CreateTableSql, DbType, Froms, FullText, IntoSqlSeg, LogicFieldType,
+            OrderBy, SqlExpr, SqlReader,
+        };
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let create = CreateTableSql {
+            table_name: "article",
+            fields: &[
+                CreateTableField {
+                    name: "id",
+                    kind: LogicFieldType::I64,
+                    not_null: true,
+                },
+                CreateTableField {
+                    name: "title",
+                    kind: LogicFieldType::Text,
+                    not_null: true,
+                },
+                CreateTableField {
+                    name: "body",
+                    kind: LogicFieldType::Text,
+                    not_null: false,
+                },
+            ],
+            pkey: &["id"],
+            unikeys: &[],
+            keys: &[],
+            fts: &["title", "body"],
+        };
+        for sql in create.to_owned_sql().sqls(DbType::Sqlite).unwrap() {
+            conn.execute(&sql, []).unwrap();
+        }
+        for (id, title, body) in [
+            (1, "rust sql builder", "build \"queries\" in rust"),
+            (2, "rust", "rust rust rust"),
+            (3, "cooking", "no code here"),
+        ] {
+            conn.execute("insert into article values(?, ?, ?)", (id, title, body))
+                .unwrap();
+        }
+        conn.execute("update article set body = 'sql again' where id = 3", [])
+            .unwrap();
+        conn.execute("delete from article where id = 1", []).unwrap();
+
+        let search = |text: &str| -> Vec<i64> {
+            let index = FullText::new("article", ["title", "body"]).alias("a");
+            let ss = SqlReader::builder(
+                [
+                    SqlExpr::col("id").with_alias("id"),
+                    index.rank(text).with_alias("rank"),
+                ],
+                Froms::Table {
+                    table_name: "article",
+                    alias: "a",
+                },
+            )
+            .wheres(index.matches(text))
+            .order_by([OrderBy::Desc("rank".into())])
+            .build()
+            .into_sql_seg(DbType::Sqlite)
+            .unwrap();
+            let mut stmt = conn.prepare(&ss.seg).unwrap();
+            stmt.query_map(rusqlite::params_from_iter(ss.values.iter()), |row| {
+                row.get(0)
+            })
+            .unwrap()
+            .map(|e| e.unwrap())
+            .collect()
+        };
+        assert_eq!(vec![2], search("rust"));
+        assert_eq!(vec![3], search("SQL"));
+        assert_eq!(Vec::<i64>::new(), search("\"queries\" OR"));
+        let mut all = search("  ");
+        all.sort();
+        assert_eq!(vec![2, 3], all);
+    }
+
+    #[test]
+    fn json() {
+        use std::collections::BTreeMap;
+
+        use crate::{DbType, IntoSqlSeg, SqlBuilder, SqlValue, Wheres, json_type::Json};
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute("create table note (id integer not null, meta text)", [])
+            .unwrap();
+        for (id, kind, tags) in [(1, "todo", vec!["a", "b"]), (2, "memo", vec!["b"])] {
+            let meta = Json(BTreeMap::from([
+                ("kind".to_owned(), vec![kind.to_owned()]),
+                (
+                    "tags".to_owned(),
+                    tags.into_iter().map(String::from).collect(),
+                ),
+            ]));
+            conn.execute(
+                "insert into note values(?, ?)",
+                (id, meta.to_sql_value().unwrap()),
+            )
+            .unwrap();
+        }
+
+        let ss = SqlBuilder::new()
+            .seg("select id, meta from note")
+            .r#where(Wheres::and([
+                Wheres::json_path_eq("meta", ["tags", "1"], "b"),
+                Wheres::json_path("meta", ["kind", "0"], "<>", "memo"),
+            ]))
+            .into_sql_seg(DbType::Sqlite)
+            .unwrap();
+        let (id, meta): (i64, SqlValue) = conn
+            .query_row(
+                &ss.seg,
+                rusqlite::params_from_iter(ss.values.iter()),
+                |row| Ok((row.get(0)?, row.get::<_, rusqlite::types::Value>(1)?.into())),
+            )
+            .unwrap();
+        let meta: Json<BTreeMap<String, Vec<String>>> = meta.try_into().unwrap();
+        assert_eq!(1, id);
+        assert_eq!(vec!["a", "b"], meta["tags"]);
+    }
 }
@@ -54,9 +54,11 @@ impl TryFrom<i64> for Timestamptz {
         } else {
             FixedOffset::west_opt(raw_tz * 60)
         };
-        let tz = tz.ok_or(ChinSqlError::TransformError(format!(
-            "unable to extract tz from {raw_tz} -- {value}"
-        )))?;
+        let out_of_range = || ChinSqlError::OutOfRange {
+            target: "Timestamptz".to_owned(),
+            value: value.to_string(),
+        };
+        let tz = tz.ok_or_else(out_of_range)?;
 
         let raw_time = value / 10000;
         let mills = (raw_time % 1000) as i32;
@@ -64,25 +66,17 @@ impl TryFrom<i64> for Timestamptz {
 
         let naive_time =
             NaiveTime::from_num_seconds_from_midnight_opt(seconds as u32, mills as u32 * 1000000)
-                .ok_or(ChinSqlError::TransformError(
-                "unable to build naive time".to_owned(),
-            ))?;
+                .ok_or_else(out_of_range)?;
 
         let yo = raw_time / 100000000;
         let y = yo / 1000;
         let o = yo % 1000;
 
         let navie_date = NaiveDate::from_yo_opt(
-            y.try_into().map_err(|err: std::num::TryFromIntError| {
-                ChinSqlError::TransformError(err.to_string())
-            })?,
-            o.try_into().map_err(|err: std::num::TryFromIntError| {
-                ChinSqlError::TransformError(err.to_string())
-            })?,
+            y.try_into().map_err(|_| out_of_range())?,
+            o.try_into().map_err(|_| out_of_range())?,
         )
-        .ok_or(ChinSqlError::TransformError(
-            "unable to build naive time".to_owned(),
-        ))?;
+        .ok_or_else(out_of_range)?;
 
         Ok(Self(tz.from_utc_datetime(&NaiveDateTime::new(
             navie_date, naive_time,
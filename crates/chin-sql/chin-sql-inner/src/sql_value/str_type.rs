@@ -43,11 +43,10 @@ impl<const LIMIT: usize> TryFrom<String> for Varchar<LIMIT> {
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
         if value.len() > LIMIT {
-            return Err(ChinSqlError::TransformError(format!(
-                "out of ranch: {} > {}",
-                value.len(),
-                LIMIT
-            )));
+            return Err(ChinSqlError::OutOfRange {
+                target: format!("Varchar<{LIMIT}>"),
+                value: format!("{} bytes", value.len()),
+            });
         }
 
         Ok(Self(SharedStr::from(value)))
@@ -59,11 +58,10 @@ impl<const LIMIT: usize> TryFrom<&'static str> for Varchar<LIMIT> {
 
     fn try_from(value: &'static str) -> Result<Self, Self::Error> {
         if value.len() > LIMIT {
-            return Err(ChinSqlError::TransformError(format!(
-                "out of ranch: {} > {}",
-                value.len(),
-                LIMIT
-            )));
+            return Err(ChinSqlError::OutOfRange {
+                target: format!("Varchar<{LIMIT}>"),
+                value: format!("{} bytes", value.len()),
+            });
         }
 
         Ok(Self(SharedStr::from(value)))
@@ -144,10 +142,22 @@ impl<'a, const LIMIT: usize> TryFrom<SqlValue<'a>> for Varchar<LIMIT> {
                 let s = cow.to_string();
                 Ok(s.try_into()?)
             }
-            other => Err(ChinSqlError::TransformError(format!(
-                "Cannot transform {other:?} into Varchar"
-            ))),
+            other => Err(ChinSqlError::UnexpectedType {
+                expected: "Varchar",
+                found: other.type_name(),
+            }),
         }
     }
 }
 
+
+impl<'a, const LIMIT: usize> TryFrom<SqlValue<'a>> for Option<Varchar<LIMIT>> {
+    type Error = ChinSqlError;
+
+    fn try_from(value: SqlValue<'a>) -> Result<Self, Self::Error> {
+        match value {
+            SqlValue::Null(_) | SqlValue::NullUnknown => Ok(None),
+            other => Ok(Some(other.try_into()?)),
+        }
+    }
+}
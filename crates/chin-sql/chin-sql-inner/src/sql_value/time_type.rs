@@ -80,10 +80,10 @@ impl TryFrom<i64> for TID {
         if value < 9_007_199_254_740_991 || value == TID_NEVER {
             Ok(Self(value))
         } else {
-            Err(ChinSqlError::TransformError(format!(
-                "value {} cannot fit into TID, its too large.",
-                value
-            )))
+            Err(ChinSqlError::OutOfRange {
+                target: "TID".to_owned(),
+                value: value.to_string(),
+            })
         }
     }
 }
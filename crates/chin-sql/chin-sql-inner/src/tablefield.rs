@@ -4,7 +4,16 @@ use std::{
     ops::{Deref, DerefMut},
 };
 
-use crate::{ILikeType, SqlBuilder, SqlValue, Wheres, str_type::Text};
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::Serialize;
+
+use crate::{
+    AggregateFunc, ChinSqlError, ILikeType, SqlBuilder, SqlExpr, SqlKey, SqlReader, SqlValue,
+    Wheres,
+    json_type::Json,
+    str_type::{Text, Varchar},
+    time_type::TID,
+};
 
 pub trait SqlTable<'a> {
     fn table_expr(&self) -> SqlBuilder<'a>;
@@ -68,13 +77,126 @@ impl<'a, T> SqlTypedField<'a, T> {
         }
     }
 
-    pub fn twn(&self) -> Cow<'a, str> {
-        format!("{}.{}", self.table_alias, self.field_name).into()
+    /// `alias.column` as the key of a [`Wheres`], quoted when built.
+    pub fn twn(&self) -> SqlKey<'a> {
+        SqlKey::path([self.table_alias, self.field_name])
     }
 
     pub fn erased(&self) -> SqlField<'a> {
         self.field.clone()
     }
+
+    pub fn expr(&self) -> SqlExpr<'a> {
+        SqlExpr::Column(self.erased())
+    }
+
+    pub fn count(&self) -> SqlExpr<'a> {
+        SqlExpr::aggregate(AggregateFunc::Count, false, self.expr())
+    }
+
+    pub fn count_distinct(&self) -> SqlExpr<'a> {
+        SqlExpr::aggregate(AggregateFunc::Count, true, self.expr())
+    }
+
+    pub fn max(&self) -> SqlExpr<'a> {
+        SqlExpr::aggregate(AggregateFunc::Max, false, self.expr())
+    }
+
+    pub fn min(&self) -> SqlExpr<'a> {
+        SqlExpr::aggregate(AggregateFunc::Min, false, self.expr())
+    }
+
+    pub fn sum(&self) -> SqlExpr<'a> {
+        SqlExpr::aggregate(AggregateFunc::Sum, false, self.expr())
+    }
+
+    pub fn avg(&self) -> SqlExpr<'a> {
+        SqlExpr::aggregate(AggregateFunc::Avg, false, self.expr())
+    }
+}
+
+/// Column types that can be compared with `<`, `>` and `between`, the
+/// comparison value of a nullable column is the inner type.
+pub trait SqlOrdered {
+    type Value;
+}
+
+/// Column types that can be searched with `like`.
+pub trait SqlTextual {}
+
+/// Column types holding JSON, see [`Json`].
+pub trait SqlJson {}
+
+macro_rules! sql_ordered {
+    ($($tp:ty),*) => {
+        $(
+            impl SqlOrdered for $tp {
+                type Value = $tp;
+            }
+
+            impl SqlOrdered for Option<$tp> {
+                type Value = $tp;
+            }
+        )*
+    };
+}
+
+sql_ordered!(
+    i8,
+    i16,
+    i32,
+    i64,
+    f32,
+    f64,
+    TID,
+    DateTime<FixedOffset>,
+    DateTime<Utc>,
+    Text,
+    String
+);
+
+impl<const LIMIT: usize> SqlOrdered for Varchar<LIMIT> {
+    type Value = Varchar<LIMIT>;
+}
+
+impl<const LIMIT: usize> SqlOrdered for Option<Varchar<LIMIT>> {
+    type Value = Varchar<LIMIT>;
+}
+
+impl SqlTextual for Text {}
+impl SqlTextual for Option<Text> {}
+impl SqlTextual for String {}
+impl SqlTextual for Option<String> {}
+impl<const LIMIT: usize> SqlTextual for Varchar<LIMIT> {}
+impl<const LIMIT: usize> SqlTextual for Option<Varchar<LIMIT>> {}
+
+impl<T> SqlJson for Json<T> {}
+impl<T> SqlJson for Option<Json<T>> {}
+
+impl<'a, T> SqlTypedField<'a, T> {
+    pub fn v_is_null(&self) -> Wheres<'a> {
+        Wheres::is_null(self.twn())
+    }
+
+    pub fn v_is_not_null(&self) -> Wheres<'a> {
+        Wheres::is_not_null(self.twn())
+    }
+
+    pub fn f_compare<U>(&self, operator: &'a str, other: &SqlTypedField<'a, U>) -> Wheres<'a> {
+        Wheres::columns(self.twn(), operator, other.twn())
+    }
+
+    pub fn f_eq<U>(&self, other: &SqlTypedField<'a, U>) -> Wheres<'a> {
+        self.f_compare("=", other)
+    }
+
+    pub fn v_in_sub(&self, query: SqlReader<'a>) -> Wheres<'a> {
+        Wheres::in_sub(self.twn(), query)
+    }
+
+    pub fn v_compare_sub(&self, operator: &'a str, query: SqlReader<'a>) -> Wheres<'a> {
+        Wheres::compare_sub(self.twn(), operator, query)
+    }
 }
 
 impl<'a, T: 'a> SqlTypedField<'a, T>
@@ -85,31 +207,244 @@ where
         Wheres::equal(self.twn(), v.into())
     }
 
+    pub fn v_ne<V: Into<T>>(&self, v: V) -> Wheres<'a> {
+        Wheres::compare(self.twn(), "<>", v.into())
+    }
+
     pub fn v_in<V: Into<T>>(&self, vs: Vec<V>) -> Wheres<'a> {
         Wheres::r#in(self.twn(), vs.into_iter().map(|v| v.into()).collect())
     }
+
+    pub fn v_not_in<V: Into<T>>(&self, vs: Vec<V>) -> Wheres<'a> {
+        Wheres::not(self.v_in(vs))
+    }
+
+    pub fn v_in_array<V: Into<T>>(&self, vs: Vec<V>) -> Wheres<'a> {
+        Wheres::in_array(self.twn(), vs.into_iter().map(|v| v.into()).collect())
+    }
 }
 
-impl<'a> SqlTypedField<'a, Text> {
+impl<'a, T> SqlTypedField<'a, T>
+where
+    T: SqlTextual,
+{
     pub fn v_ilike<V: AsRef<str>>(&self, v: V, exact: ILikeType) -> Wheres<'a> {
         Wheres::ilike(self.twn(), v.as_ref(), exact)
     }
+
+    pub fn v_unicode_ilike<V: AsRef<str>>(&self, v: V, exact: ILikeType) -> Wheres<'a> {
+        Wheres::unicode_ilike(self.twn(), v.as_ref(), exact)
+    }
+}
+
+impl<'a, T> SqlTypedField<'a, T>
+where
+    T: SqlJson,
+{
+    pub fn v_json_path<P, V>(&self, path: P, operator: &'a str, v: V) -> Wheres<'a>
+    where
+        P: IntoIterator,
+        P::Item: Into<Cow<'a, str>>,
+        V: Into<SqlValue<'a>>,
+    {
+        Wheres::json_path(self.twn(), path, operator, v)
+    }
+
+    pub fn v_json_path_eq<P, V>(&self, path: P, v: V) -> Wheres<'a>
+    where
+        P: IntoIterator,
+        P::Item: Into<Cow<'a, str>>,
+        V: Into<SqlValue<'a>>,
+    {
+        Wheres::json_path_eq(self.twn(), path, v)
+    }
+
+    /// `v` may be any part of the document, not necessarily a whole one.
+    pub fn v_json_contains<V: Serialize>(&self, v: V) -> Result<Wheres<'a>, ChinSqlError> {
+        Wheres::json_contains(self.twn(), v)
+    }
 }
 
-impl<'a> SqlTypedField<'a, i64> {
-    pub fn v_gt<V: Into<i64>>(&self, v: V) -> Wheres<'a> {
+impl<'a, T> SqlTypedField<'a, T>
+where
+    T: SqlOrdered,
+    T::Value: Into<SqlValue<'a>>,
+{
+    pub fn v_gt<V: Into<T::Value>>(&self, v: V) -> Wheres<'a> {
         Wheres::compare(self.twn(), ">", v.into())
     }
 
-    pub fn v_lt<V: Into<i64>>(&self, v: V) -> Wheres<'a> {
+    pub fn v_lt<V: Into<T::Value>>(&self, v: V) -> Wheres<'a> {
         Wheres::compare(self.twn(), "<", v.into())
     }
 
-    pub fn v_ge<V: Into<i64>>(&self, v: V) -> Wheres<'a> {
+    pub fn v_ge<V: Into<T::Value>>(&self, v: V) -> Wheres<'a> {
         Wheres::compare(self.twn(), ">=", v.into())
     }
 
-    pub fn v_le<V: Into<i64>>(&self, v: V) -> Wheres<'a> {
+    pub fn v_le<V: Into<T::Value>>(&self, v: V) -> Wheres<'a> {
         Wheres::compare(self.twn(), "<=", v.into())
     }
+
+    pub fn v_between<V: Into<T::Value>>(&self, low: V, high: V) -> Wheres<'a> {
+        Wheres::between(self.twn(), low.into(), high.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, FixedOffset, Utc};
+
+    use crate::{
+        DbType, Froms, ILikeType, PlaceHolderType, SqlExpr, SqlReader, SqlTypedField, SqlValue,
+        Wheres,
+        str_type::{Text, Varchar},
+        test_util::squash,
+        time_type::TID,
+    };
+
+    fn render(wheres: Wheres) -> (String, usize) {
+        let ss = wheres
+            .build(DbType::Postgres, &mut PlaceHolderType::dollar_number())
+            .unwrap()
+            .unwrap();
+        (squash(&ss.seg), ss.values.len())
+    }
+
+    #[test]
+    fn typed_predicates() {
+        let created = SqlTypedField::<DateTime<FixedOffset>>::new("n", "created_at");
+        let deleted = SqlTypedField::<Option<DateTime<Utc>>>::new("n", "deleted_at");
+        let id = SqlTypedField::<TID>::new("n", "id");
+        let score = SqlTypedField::<Option<f64>>::new("n", "score");
+        let name = SqlTypedField::<Varchar<20>>::new("n", "name");
+        let pinned = SqlTypedField::<bool>::new("n", "pinned");
+
+        let now = Utc::now();
+        assert_eq!(
+            ("\"n\".\"created_at\" between $1 and $2".to_owned(), 2),
+            render(created.v_between(now.fixed_offset(), now.fixed_offset()))
+        );
+        assert_eq!(
+            ("\"n\".\"deleted_at\" < $1".to_owned(), 1),
+            render(deleted.v_lt(now))
+        );
+        assert_eq!(
+            ("not ( \"n\".\"id\" in ($1,$2))".to_owned(), 2),
+            render(id.v_not_in(vec![TID::never(), TID::default()]))
+        );
+        assert_eq!(
+            ("\"n\".\"score\" >= $1".to_owned(), 1),
+            render(score.v_ge(1.5))
+        );
+        assert_eq!(
+            ("\"n\".\"score\" is null".to_owned(), 0),
+            render(score.v_is_null())
+        );
+        assert_eq!(
+            ("\"n\".\"name\" ilike $1 escape '!'".to_owned(), 1),
+            render(name.v_ilike("abc", ILikeType::RightFuzzy))
+        );
+        assert_eq!(
+            ("\"n\".\"pinned\" <> $1".to_owned(), 1),
+            render(pinned.v_ne(true))
+        );
+    }
+
+    #[test]
+    fn column_and_subquery_predicates() {
+        let note_id = SqlTypedField::<i64>::new("n", "id");
+        let owner = SqlTypedField::<i64>::new("n", "owner");
+        let tag_note_id = SqlTypedField::<i64>::new("t", "note_id");
+        let tag_name = SqlTypedField::<Text>::new("t", "name");
+        let tags = || Froms::Table {
+            table_name: "tag",
+            alias: "t",
+        };
+
+        let wheres = Wheres::and([
+            owner.v_eq(1),
+            Wheres::exists(
+                SqlReader::builder([tag_note_id.erased()], tags())
+                    .wheres(Wheres::and([
+                        tag_note_id.f_eq(&note_id),
+                        tag_name.v_eq(Text::from("rust".to_owned())),
+                    ]))
+                    .build(),
+            ),
+            Wheres::not_exists(
+                SqlReader::builder([tag_note_id.erased()], tags())
+                    .wheres(tag_note_id.f_compare("<>", &note_id))
+                    .build(),
+            ),
+            note_id.v_in_sub(
+                SqlReader::builder([tag_note_id.erased()], tags())
+                    .wheres(tag_name.v_ne(Text::from("draft".to_owned())))
+                    .build(),
+            ),
+            owner.v_compare_sub(
+                ">",
+                SqlReader::builder([SqlExpr::count_all()], tags()).build(),
+            ),
+        ]);
+
+        assert_eq!(
+            (
+                "\"n\".\"owner\" = $1 \
+                 and exists (select \"t\".\"note_id\" from \"tag\" as \"t\" where \"t\".\"note_id\" = \"n\".\"id\" and \"t\".\"name\" = $2) \
+                 and not exists (select \"t\".\"note_id\" from \"tag\" as \"t\" where \"t\".\"note_id\" <> \"n\".\"id\") \
+                 and \"n\".\"id\" in (select \"t\".\"note_id\" from \"tag\" as \"t\" where \"t\".\"name\" <> $3) \
+                 and \"n\".\"owner\" > (select count(*) from \"tag\" as \"t\")"
+                    .to_owned(),
+                3
+            ),
+            render(wheres)
+        );
+    }
+
+    #[test]
+    fn in_lists() {
+        let id = SqlTypedField::<i64>::new("n", "id");
+
+        assert_eq!(("1 = 0".to_owned(), 0), render(id.v_in(Vec::<i64>::new())));
+        assert_eq!(
+            ("not ( 1 = 0)".to_owned(), 0),
+            render(id.v_not_in(Vec::<i64>::new()))
+        );
+        assert_eq!(
+            ("\"n\".\"id\" = any($1)".to_owned(), 1),
+            render(id.v_in_array(vec![1, 2, 3]))
+        );
+        assert_eq!(
+            ("\"n\".\"id\" = any($1)".to_owned(), 1),
+            render(id.v_in((0..2000).collect()))
+        );
+
+        let ss = id
+            .v_in((0..2000).collect())
+            .build(DbType::Sqlite, &mut PlaceHolderType::question_mark())
+            .unwrap()
+            .unwrap();
+        assert_eq!("\"n\".\"id\" in (select value from json_each(?))", ss.seg);
+        assert_eq!(1, ss.values.len());
+
+        let mixed = Wheres::r#in(
+            "n.id",
+            (0..2000)
+                .map(|e| {
+                    if e % 2 == 0 {
+                        SqlValue::I64(e)
+                    } else {
+                        SqlValue::from("x")
+                    }
+                })
+                .collect(),
+        );
+        assert_eq!(2000, render(mixed).1);
+        assert!(
+            Wheres::in_array("n.id", vec![SqlValue::I64(1), SqlValue::from("x")])
+                .build(DbType::Postgres, &mut PlaceHolderType::dollar_number())
+                .is_err()
+        );
+    }
 }
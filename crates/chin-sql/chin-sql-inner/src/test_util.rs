@@ -0,0 +1,5 @@
+/// Collapses the whitespace of rendered SQL, the builders pad their segments
+/// with spaces.
+pub(crate) fn squash(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
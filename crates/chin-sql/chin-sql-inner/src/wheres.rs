@@ -1,15 +1,59 @@
 use std::borrow::Cow;
 
-use crate::{DbType, PlaceHolderType, SegOrVal, SqlSeg};
+use serde::Serialize;
+
+use crate::{
+    ChinSqlError, DbType, FullText, IntoSqlSeg, PlaceHolderType, SegOrVal, SqlArray, SqlKey,
+    SqlReader, SqlSeg, json_type::Json,
+};
 
 use super::sql_value::SqlValue;
 
+/// Longer `in` lists are bound as a single array parameter so they stay
+/// below SQLite's default limit of bound parameters.
+pub const IN_LIST_ARRAY_THRESHOLD: usize = 999;
+
+/// Rendered for an empty `in` list.
+const ALWAYS_FALSE: &str = "1 = 0";
+
+/// The `escape` character of [`Wheres::ilike`], chosen over `\` which
+/// MySQL would read as an escape in the string literal itself.
+pub const LIKE_ESCAPE: char = '!';
+
+/// Escape `%`, `_` and [`LIKE_ESCAPE`] so `s` only matches itself in a
+/// `like ... escape '!'` pattern.
+pub fn escape_like(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '%' | '_' | LIKE_ESCAPE) {
+            out.push(LIKE_ESCAPE);
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// The escaped pattern of an ilike, `None` for an empty search text.
+fn like_pattern(s: &str, exact: ILikeType) -> Option<String> {
+    if s.is_empty() {
+        return None;
+    }
+    Some(match exact {
+        ILikeType::Original => escape_like(s),
+        ILikeType::RightFuzzy => format!("{}%", escape_like(s)),
+        ILikeType::LeftFuzzy => format!("%{}", escape_like(s)),
+        ILikeType::Fuzzy => format!("%{}%", escape_like(s)),
+    })
+}
+
 #[derive(Clone, Debug)]
 pub enum WhereConjOp {
     And,
     Or,
 }
 
+/// Where the search text of [`Wheres::ilike`] may be found, the text itself
+/// is escaped so `%` and `_` in it match literally.
 pub enum ILikeType {
     Original,
     RightFuzzy,
@@ -50,24 +94,64 @@ impl FilterCount {
 #[derive(Clone, Debug)]
 pub enum Wheres<'a> {
     Conj(WhereConjOp, Vec<Wheres<'a>>),
-    In(Cow<'a, str>, Vec<SqlValue<'a>>),
+    In(SqlKey<'a>, Vec<SqlValue<'a>>),
+    /// Bound as one array, `= any($1)` on Postgres and `json_each` on SQLite.
+    InArray(SqlKey<'a>, Vec<SqlValue<'a>>),
     Not(Box<Wheres<'a>>),
     Compare {
-        key: Cow<'a, str>,
+        key: SqlKey<'a>,
         operator: Cow<'a, str>,
         value: SqlValue<'a>,
     }, // key, operator, value
     Raw(Cow<'a, str>),
     SOV(Vec<SegOrVal<'a>>),
     IIike {
-        key: Cow<'a, str>,
+        key: SqlKey<'a>,
         value: String,
     },
+    /// See [`Wheres::unicode_ilike`].
+    UnicodeIlike {
+        key: SqlKey<'a>,
+        value: String,
+    },
+    Columns {
+        left: SqlKey<'a>,
+        operator: Cow<'a, str>,
+        right: SqlKey<'a>,
+    },
+    Exists(Box<SqlReader<'a>>),
+    NotExists(Box<SqlReader<'a>>),
+    InSub {
+        key: SqlKey<'a>,
+        query: Box<SqlReader<'a>>,
+    },
+    CompareSub {
+        key: SqlKey<'a>,
+        operator: Cow<'a, str>,
+        query: Box<SqlReader<'a>>,
+    },
+    /// See [`FullText::matches`].
+    FullText {
+        index: FullText<'a>,
+        text: String,
+    },
+    /// See [`Wheres::json_path`].
+    JsonPath {
+        key: SqlKey<'a>,
+        path: Vec<Cow<'a, str>>,
+        operator: Cow<'a, str>,
+        value: SqlValue<'a>,
+    },
+    /// See [`Wheres::json_contains`].
+    JsonContains {
+        key: SqlKey<'a>,
+        value: SqlValue<'a>,
+    },
     None,
 }
 
 impl<'a> Wheres<'a> {
-    pub fn equal<T: Into<SqlValue<'a>>, S: Into<Cow<'a, str>>>(key: S, v: T) -> Self {
+    pub fn equal<T: Into<SqlValue<'a>>, S: Into<SqlKey<'a>>>(key: S, v: T) -> Self {
         Self::Compare {
             key: key.into(),
             operator: "=".into(),
@@ -75,30 +159,83 @@ impl<'a> Wheres<'a> {
         }
     }
 
-    pub fn ilike<T: AsRef<str>, S: Into<Cow<'a, str>>>(key: S, v: T, exact: ILikeType) -> Self {
-        let s = v.as_ref();
-        if s.is_empty() {
-            return Wheres::None;
+    pub fn ilike<T: AsRef<str>, S: Into<SqlKey<'a>>>(key: S, v: T, exact: ILikeType) -> Self {
+        match like_pattern(v.as_ref(), exact) {
+            Some(value) => Self::IIike {
+                key: key.into(),
+                value,
+            },
+            None => Wheres::None,
         }
-        Self::IIike {
-            key: key.into(),
-            value: match exact {
-                ILikeType::Original => v.as_ref().into(),
-                ILikeType::RightFuzzy => format!("{}%", v.as_ref()),
-                ILikeType::LeftFuzzy => format!("%{}", v.as_ref()),
-                ILikeType::Fuzzy => format!("%{}%", v.as_ref()),
+    }
+
+    /// [`Wheres::ilike`] folding case beyond ASCII on SQLite too, through
+    /// the `unicode_lower` function the connection must have registered,
+    /// e.g. by actor-sqlite's pool. Other dialects render a plain ilike.
+    pub fn unicode_ilike<T: AsRef<str>, S: Into<SqlKey<'a>>>(
+        key: S,
+        v: T,
+        exact: ILikeType,
+    ) -> Self {
+        match like_pattern(v.as_ref(), exact) {
+            Some(value) => Self::UnicodeIlike {
+                key: key.into(),
+                value,
             },
+            None => Wheres::None,
         }
     }
-    pub fn is_null<S: Into<Cow<'a, str>>>(key: S) -> Self {
-        Self::compare_str(key.into(), "is", "null")
+
+    /// Compare the scalar at `path` inside the JSON column `key`, elements
+    /// made of digits index arrays. `json_extract` on SQLite and `->>` on
+    /// Postgres, cast to the type of `v`.
+    pub fn json_path<S, P, O, T>(key: S, path: P, operator: O, v: T) -> Self
+    where
+        S: Into<SqlKey<'a>>,
+        P: IntoIterator,
+        P::Item: Into<Cow<'a, str>>,
+        O: Into<Cow<'a, str>>,
+        T: Into<SqlValue<'a>>,
+    {
+        Self::JsonPath {
+            key: key.into(),
+            path: path.into_iter().map(Into::into).collect(),
+            operator: operator.into(),
+            value: v.into(),
+        }
     }
 
-    pub fn is_not_null<S: Into<Cow<'a, str>>>(key: S) -> Self {
-        Self::compare_str(key.into(), "is not", "null")
+    pub fn json_path_eq<S, P, T>(key: S, path: P, v: T) -> Self
+    where
+        S: Into<SqlKey<'a>>,
+        P: IntoIterator,
+        P::Item: Into<Cow<'a, str>>,
+        T: Into<SqlValue<'a>>,
+    {
+        Self::json_path(key, path, "=", v)
     }
 
-    pub fn compare<SK: Into<Cow<'a, str>>, SO: Into<Cow<'a, str>>, T: Into<SqlValue<'a>>>(
+    /// The JSON column `key` contains `v` serialized as a JSON document.
+    /// `@>` on Postgres, SQLite has no containment operator.
+    pub fn json_contains<S: Into<SqlKey<'a>>, T: Serialize>(
+        key: S,
+        v: T,
+    ) -> Result<Self, ChinSqlError> {
+        Ok(Self::JsonContains {
+            key: key.into(),
+            value: Json(v).to_sql_value()?,
+        })
+    }
+
+    pub fn is_null<S: Into<SqlKey<'a>>>(key: S) -> Self {
+        Self::compare_str(key, "is", "null")
+    }
+
+    pub fn is_not_null<S: Into<SqlKey<'a>>>(key: S) -> Self {
+        Self::compare_str(key, "is not", "null")
+    }
+
+    pub fn compare<SK: Into<SqlKey<'a>>, SO: Into<Cow<'a, str>>, T: Into<SqlValue<'a>>>(
         key: SK,
         operator: SO,
         v: T,
@@ -110,17 +247,25 @@ impl<'a> Wheres<'a> {
         }
     }
 
-    pub fn compare_str<T: AsRef<str>, S: Into<Cow<'a, str>>>(
+    pub fn between<S: Into<SqlKey<'a>>, T: Into<SqlValue<'a>>>(key: S, low: T, high: T) -> Self {
+        Self::SOV(vec![
+            SegOrVal::key(key),
+            " between ".into(),
+            SegOrVal::val(low),
+            " and ".into(),
+            SegOrVal::val(high),
+        ])
+    }
+
+    pub fn compare_str<T: AsRef<str>, S: Into<SqlKey<'a>>>(
         key: S,
         operator: &'a str,
         v: T,
     ) -> Self {
-        Self::Raw(Cow::Owned(format!(
-            "{} {} {}",
-            key.into(),
-            operator,
-            v.as_ref()
-        )))
+        Self::SOV(vec![
+            SegOrVal::key(key),
+            format!(" {} {}", operator, v.as_ref()).into(),
+        ])
     }
 
     pub fn if_some<T, F>(original: Option<T>, map: F) -> Self
@@ -152,31 +297,90 @@ impl<'a> Wheres<'a> {
         map(original)
     }
 
-    pub fn r#in<T: Into<SqlValue<'a>>, S: Into<Cow<'a, str>>>(key: S, values: Vec<T>) -> Self {
+    pub fn r#in<T: Into<SqlValue<'a>>, S: Into<SqlKey<'a>>>(key: S, values: Vec<T>) -> Self {
         Self::In(key.into(), values.into_iter().map(|e| e.into()).collect())
     }
 
+    pub fn in_array<T: Into<SqlValue<'a>>, S: Into<SqlKey<'a>>>(key: S, values: Vec<T>) -> Self {
+        Self::InArray(key.into(), values.into_iter().map(|e| e.into()).collect())
+    }
+
+    pub fn columns<SL: Into<SqlKey<'a>>, SO: Into<Cow<'a, str>>, SR: Into<SqlKey<'a>>>(
+        left: SL,
+        operator: SO,
+        right: SR,
+    ) -> Self {
+        Self::Columns {
+            left: left.into(),
+            operator: operator.into(),
+            right: right.into(),
+        }
+    }
+
+    pub fn exists(query: SqlReader<'a>) -> Self {
+        Self::Exists(Box::new(query))
+    }
+
+    pub fn not_exists(query: SqlReader<'a>) -> Self {
+        Self::NotExists(Box::new(query))
+    }
+
+    pub fn in_sub<S: Into<SqlKey<'a>>>(key: S, query: SqlReader<'a>) -> Self {
+        Self::InSub {
+            key: key.into(),
+            query: Box::new(query),
+        }
+    }
+
+    pub fn compare_sub<SK: Into<SqlKey<'a>>, SO: Into<Cow<'a, str>>>(
+        key: SK,
+        operator: SO,
+        query: SqlReader<'a>,
+    ) -> Self {
+        Self::CompareSub {
+            key: key.into(),
+            operator: operator.into(),
+            query: Box::new(query),
+        }
+    }
+
     pub fn none() -> Self {
         Self::None
     }
 
-    pub fn build(self, db_type: DbType, value_type: &mut PlaceHolderType) -> Option<SqlSeg<'a>> {
+    fn array_predicate(key: &str, db_type: DbType, value_type: &mut PlaceHolderType) -> String {
+        db_type
+            .dialect()
+            .array_predicate(key, &value_type.next_ph())
+    }
+
+    /// Returns `Ok(None)` when the filter collapses to nothing. Fails on an
+    /// invalid column or an unbound `:name` rather than dropping the filter,
+    /// callers of the former `Option` returning version add a `?`.
+    pub fn build(
+        self,
+        db_type: DbType,
+        value_type: &mut PlaceHolderType,
+    ) -> Result<Option<SqlSeg<'a>>, ChinSqlError> {
         let mut seg = String::new();
         let mut values: Vec<SqlValue<'a>> = Vec::new();
 
         match self {
             Wheres::Conj(op, fs) => {
-                let vs: Vec<String> = fs
-                    .into_iter()
-                    .filter_map(|e| {
-                        e.build(db_type, value_type).map(|ss| {
-                            values.extend(ss.values);
-                            ss.seg
-                        })
-                    })
-                    .collect();
+                let mut vs: Vec<String> = Vec::with_capacity(fs.len());
+                for e in fs {
+                    let nested = matches!(e, Wheres::Conj(..));
+                    if let Some(ss) = e.build(db_type, value_type)? {
+                        values.extend(ss.values);
+                        if nested {
+                            vs.push(format!("({})", ss.seg));
+                        } else {
+                            vs.push(ss.seg);
+                        }
+                    }
+                }
                 if vs.is_empty() {
-                    return None;
+                    return Ok(None);
                 }
                 let op = match op {
                     WhereConjOp::And => " and ",
@@ -185,39 +389,64 @@ impl<'a> Wheres<'a> {
 
                 seg.push_str(vs.join(op).as_str())
             }
+            Wheres::In(_, fs) if fs.is_empty() => {
+                seg.push_str(ALWAYS_FALSE);
+            }
             Wheres::In(key, fs) => {
-                log::info!("print: {key:?}, {fs:?}");
-                seg.push_str(key.as_ref());
-                seg.push_str(" in (");
-                let vs = fs
-                    .iter()
-                    .map(|_| value_type.next_ph())
-                    .collect::<Vec<String>>();
-                seg.push_str(vs.join(",").as_str());
-
-                seg.push(')');
-                values.extend(fs)
+                let key = key.render(db_type)?;
+                // a mixed list cannot be bound as one array, expand it anyway
+                let arr = if fs.len() > IN_LIST_ARRAY_THRESHOLD {
+                    SqlArray::try_from(fs.clone()).ok()
+                } else {
+                    None
+                };
+                match arr {
+                    Some(arr) => {
+                        seg.push_str(&Self::array_predicate(&key, db_type, value_type));
+                        values.push(arr.into());
+                    }
+                    None => {
+                        seg.push_str(key.as_ref());
+                        seg.push_str(" in (");
+                        let vs = fs
+                            .iter()
+                            .map(|_| value_type.next_ph())
+                            .collect::<Vec<String>>();
+                        seg.push_str(vs.join(",").as_str());
+
+                        seg.push(')');
+                        values.extend(fs)
+                    }
+                }
+            }
+            Wheres::InArray(_, fs) if fs.is_empty() => {
+                seg.push_str(ALWAYS_FALSE);
+            }
+            Wheres::InArray(key, fs) => {
+                let key = key.render(db_type)?;
+                seg.push_str(&Self::array_predicate(&key, db_type, value_type));
+                values.push(SqlArray::try_from(fs)?.into());
             }
             Wheres::Not(fs) => {
                 seg.push_str(" not ( ");
-                if let Some(ss) = fs.build(db_type, value_type) {
+                if let Some(ss) = fs.build(db_type, value_type)? {
                     seg.push_str(&ss.seg);
                     seg.push(')');
 
                     values.extend(ss.values);
                 } else {
-                    return None;
+                    return Ok(None);
                 }
             }
             Wheres::None => {
-                return None;
+                return Ok(None);
             }
             Wheres::Compare {
                 key,
                 operator,
                 value,
             } => {
-                seg.push_str(key.as_ref());
+                seg.push_str(&key.render(db_type)?);
                 seg.push(' ');
                 seg.push_str(operator.as_ref());
                 seg.push(' ');
@@ -235,38 +464,97 @@ impl<'a> Wheres<'a> {
             }
             Wheres::SOV(seg_or_vals) => {
                 for sov in seg_or_vals {
-                    match sov {
-                        SegOrVal::Str(cow) => {
-                            seg.push_str(&cow);
-                        }
-                        SegOrVal::Val(sql_value) => {
-                            seg.push_str(&value_type.next_ph());
-                            values.push(sql_value);
-                        }
-                    }
+                    sov.render(&mut seg, &mut values, db_type, value_type)?;
                 }
             }
             Wheres::IIike { key, value } => {
-                let ilike = match db_type {
-                    DbType::Sqlite => Self::Compare {
-                        key,
-                        operator: "like".into(),
-                        value: value.into(),
-                    },
-                    DbType::Postgres => Self::Compare {
-                        key,
-                        operator: "ilike".into(),
-                        value: value.into(),
-                    },
-                };
-                let s = ilike.build(db_type, value_type);
-                if let Some(SqlSeg { seg: s, values: v }) = s {
-                    seg.push_str(s.as_str());
-                    values.extend(v);
-                }
+                let key = key.render(db_type)?;
+                seg.push_str(
+                    &db_type
+                        .dialect()
+                        .ilike(&key, &value_type.next_ph(), LIKE_ESCAPE),
+                );
+                values.push(value.into());
+            }
+            Wheres::UnicodeIlike { key, value } => {
+                let key = key.render(db_type)?;
+                seg.push_str(&db_type.dialect().unicode_ilike(
+                    &key,
+                    &value_type.next_ph(),
+                    LIKE_ESCAPE,
+                ));
+                values.push(value.into());
+            }
+            Wheres::FullText { index, text } => {
+                let dialect = db_type.dialect();
+                seg.push_str(
+                    &dialect.full_text_match(&index.to_sql(db_type)?, &value_type.next_ph())?,
+                );
+                values.push(dialect.full_text_query(&text).into());
+            }
+            Wheres::JsonPath {
+                key,
+                path,
+                operator,
+                value,
+            } => {
+                let key = key.render(db_type)?;
+                let path: Vec<&str> = path.iter().map(|e| e.as_ref()).collect();
+                seg.push_str(&format!(
+                    "{} {operator} {}",
+                    db_type.dialect().json_extract(&key, &path, &value)?,
+                    value_type.next_ph()
+                ));
+                values.push(value);
+            }
+            Wheres::JsonContains { key, value } => {
+                let key = key.render(db_type)?;
+                seg.push_str(
+                    &db_type
+                        .dialect()
+                        .json_contains(&key, &value_type.next_ph())?,
+                );
+                values.push(value);
+            }
+            Wheres::Columns {
+                left,
+                operator,
+                right,
+            } => {
+                seg.push_str(&format!(
+                    "{} {operator} {}",
+                    left.render(db_type)?,
+                    right.render(db_type)?
+                ));
+            }
+            Wheres::Exists(query) => {
+                let SqlSeg { seg: s, values: v } = query.into_sql_seg2(db_type, value_type)?;
+                seg.push_str(&format!("exists ({})", s.trim()));
+                values.extend(v);
+            }
+            Wheres::NotExists(query) => {
+                let SqlSeg { seg: s, values: v } = query.into_sql_seg2(db_type, value_type)?;
+                seg.push_str(&format!("not exists ({})", s.trim()));
+                values.extend(v);
+            }
+            Wheres::InSub { key, query } => {
+                let key = key.render(db_type)?;
+                let SqlSeg { seg: s, values: v } = query.into_sql_seg2(db_type, value_type)?;
+                seg.push_str(&format!("{key} in ({})", s.trim()));
+                values.extend(v);
+            }
+            Wheres::CompareSub {
+                key,
+                operator,
+                query,
+            } => {
+                let key = key.render(db_type)?;
+                let SqlSeg { seg: s, values: v } = query.into_sql_seg2(db_type, value_type)?;
+                seg.push_str(&format!("{key} {operator} ({})", s.trim()));
+                values.extend(v);
             }
         }
 
-        Some(SqlSeg::of(seg, values))
+        Ok(Some(SqlSeg::of(seg, values)))
     }
 }
@@ -0,0 +1,27 @@
+use crate::{ChinSqlError, SqlSeg};
+
+/// The statement an update or delete builder would run, without refusing
+/// a missing filter.
+#[derive(Clone, Debug)]
+pub struct DryRun<'a> {
+    pub seg: SqlSeg<'a>,
+    /// No filter is left, every row of the table would be touched.
+    pub all_rows: bool,
+}
+
+/// Refuse a write whose filter collapsed to nothing unless every row is
+/// wanted on purpose.
+pub(crate) fn guard_filter<'a>(
+    filters: Option<SqlSeg<'a>>,
+    builder: &'static str,
+    table: &str,
+    all_rows: bool,
+) -> Result<Option<SqlSeg<'a>>, ChinSqlError> {
+    match filters {
+        None if !all_rows => Err(ChinSqlError::UnfilteredWrite {
+            builder,
+            table: table.to_owned(),
+        }),
+        filters => Ok(filters),
+    }
+}